@@ -1,24 +1,109 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, State};
 
 const SHIELD_DIR: &str = ".shield";
 const CONFIG_FILE: &str = "config.json";
 const INDEX_FILE: &str = "index.json";
 const SNAPSHOTS_DIR: &str = "snapshots";
 const PID_FILE: &str = "shield.pid";
+const LOG_FILE: &str = "shield.log";
+const OPERATIONS_LOG_FILE: &str = "operations.log";
+const INDEX_LOCK_FILE: &str = "index.lock";
+const SUPPORTED_INDEX_VERSION: i32 = 2;
+const INDEX_LOCK_TIMEOUT_MS: u128 = 5000;
+const INDEX_LOCK_STALE_MS: i64 = 30_000;
+const DEFAULT_WATCH_DEBOUNCE_MS: u64 = 500;
+const SHIELD_LOOKUP_TIMEOUT_MS: u64 = 2000;
+const DAEMON_FORK_WAIT_MS: u64 = 3000;
+const GRACEFUL_STOP_TIMEOUT_MS: u64 = 5000;
+const SHIELD_STATUS_POLL_MS: u64 = 2000;
+const AUTO_CLEAN_INTERVAL_MS: u64 = 60 * 60 * 1000;
+const PING_TIMEOUT_MS: u64 = 10_000;
+const QUIET_PERIOD_MAX_WAIT_MS: u64 = 30_000;
+const VERIFY_MAX_THREADS: usize = 8;
+const RESTORE_RETRY_ATTEMPTS: u32 = 3;
+const RESTORE_RETRY_BASE_MS: u64 = 100;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Workspace {
     pub path: String,
     pub name: String,
     pub added_at: i64,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub max_storage_bytes: Option<u64>,
+    #[serde(default)]
+    pub auto_clean_days: Option<i64>,
+    #[serde(default)]
+    pub last_restore: Option<(String, i64)>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WorkspaceLayout {
+    pub snapshots_dir: String,
+    pub index_file: String,
+    pub pid_file: String,
+}
+
+impl Default for WorkspaceLayout {
+    fn default() -> Self {
+        WorkspaceLayout {
+            snapshots_dir: SNAPSHOTS_DIR.to_string(),
+            index_file: INDEX_FILE.to_string(),
+            pid_file: PID_FILE.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkspacePaths {
+    pub shield_dir: String,
+    pub shield_dir_exists: bool,
+    pub index_path: String,
+    pub index_exists: bool,
+    pub snapshots_dir: String,
+    pub snapshots_dir_exists: bool,
+    pub pid_file: String,
+    pub pid_file_exists: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EffectiveSettings {
+    pub watch_debounce_ms: u64,
+    pub restore_no_delete: bool,
+    pub auto_clean_days: Option<i64>,
+    pub max_storage_bytes: Option<u64>,
+    pub storage_dir: String,
+    pub layout: WorkspaceLayout,
+    pub env: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EffectiveConfig {
+    pub stored: Option<Workspace>,
+    pub effective: EffectiveSettings,
+    pub overridden: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct GlobalConfig {
     pub workspaces: Vec<Workspace>,
+    #[serde(default)]
+    pub watch_debounce_ms: Option<u64>,
+    #[serde(default)]
+    pub restore_no_delete: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -31,6 +116,58 @@ pub struct SnapshotFile {
     pub event_type: String,
     #[serde(rename = "renamedTo")]
     pub renamed_to: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codec: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EventType {
+    Create,
+    Change,
+    Delete,
+    Rename,
+}
+
+impl EventType {
+    fn parse(value: &str) -> Result<EventType, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "create" => Ok(EventType::Create),
+            "change" => Ok(EventType::Change),
+            "delete" => Ok(EventType::Delete),
+            "rename" => Ok(EventType::Rename),
+            _ => Err(format!("unknown event type: {}", value)),
+        }
+    }
+    
+    fn label(self) -> &'static str {
+        match self {
+            EventType::Create => "Created",
+            EventType::Change => "Modified",
+            EventType::Delete => "Deleted",
+            EventType::Rename => "Renamed",
+        }
+    }
+}
+
+#[tauri::command]
+fn get_event_type_label(event_type: String) -> String {
+    if let Ok(event) = EventType::parse(&event_type) {
+        return event.label().to_string();
+    }
+    let raw = event_type.trim();
+    let mut chars = raw.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect(),
+        None => "Unknown".to_string(),
+    }
+}
+
+impl SnapshotFile {
+    fn event(&self) -> Result<EventType, String> {
+        EventType::parse(&self.event_type)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -39,6 +176,14 @@ pub struct Snapshot {
     pub timestamp: i64,
     pub files: Vec<SnapshotFile>,
     pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger: Option<String>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -55,17 +200,240 @@ pub struct WorkspaceStats {
     pub unique_files: usize,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GlobalStats {
+    pub workspaces: usize,
+    pub missing_workspaces: usize,
+    pub running: usize,
+    pub snapshots: usize,
+    pub total_size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkspaceExport {
+    pub workspace_path: String,
+    pub workspace: Option<Workspace>,
+    pub exported_at: i64,
+    pub index: BackupIndex,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SnapshotChanges {
+    pub created: Vec<SnapshotFile>,
+    pub modified: Vec<SnapshotFile>,
+    pub deleted: Vec<SnapshotFile>,
+    pub renamed: Vec<SnapshotFile>,
+    pub unknown: Vec<SnapshotFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotSession {
+    pub start: i64,
+    pub end: i64,
+    pub snapshot_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct WorkspaceComparison {
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+    pub different_size: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct VerifyReport {
+    pub ok: usize,
+    pub missing: usize,
+    pub corrupt: usize,
+    pub missing_files: Vec<String>,
+    pub corrupt_files: Vec<String>,
+    #[serde(default)]
+    pub id_repairs: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RestoreResult {
     pub restored: u32,
     pub failed: u32,
     pub deleted: u32,
+    #[serde(default)]
+    pub skipped: u32,
+    #[serde(default)]
+    pub cancelled: bool,
+    #[serde(default)]
+    pub failures: Vec<(String, String)>,
+    #[serde(default)]
+    pub conflicts: Vec<String>,
+    #[serde(default)]
+    pub conflicts_skipped: u32,
+    #[serde(default)]
+    pub pre_restore_snapshot: Option<String>,
+    #[serde(default)]
+    pub verification_failures: Vec<String>,
+    #[serde(default)]
+    pub deletes_suppressed: u32,
+    #[serde(default)]
+    pub moved_aside: Vec<(String, String)>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct RestorePlan {
+    pub to_restore: Vec<String>,
+    pub to_delete: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub excluded: Vec<String>,
+    pub conflicts: Vec<String>,
+    pub dirs_to_create: Vec<String>,
+    pub unrecognized: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct RestoreEstimate {
+    pub files: usize,
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ConflictStrategy {
+    #[default]
+    Overwrite,
+    Skip,
+    BackupThenOverwrite,
+}
+
+impl ConflictStrategy {
+    fn parse(value: Option<&str>) -> Result<ConflictStrategy, String> {
+        match value {
+            None | Some("overwrite") => Ok(ConflictStrategy::Overwrite),
+            Some("skip") => Ok(ConflictStrategy::Skip),
+            Some("backup-then-overwrite") => Ok(ConflictStrategy::BackupThenOverwrite),
+            Some(other) => Err(format!("Unknown conflict strategy: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RestoreOptions {
+    exclude: Vec<String>,
+    conflict_strategy: ConflictStrategy,
+    skip_system: bool,
+    subpath: Option<String>,
+    verify: bool,
+    // None falls back to the `restore_no_delete` setting
+    no_delete: Option<bool>,
+    move_aside_blockers: bool,
+}
+
+impl Default for RestoreOptions {
+    fn default() -> Self {
+        RestoreOptions {
+            exclude: vec![],
+            conflict_strategy: ConflictStrategy::default(),
+            skip_system: true,
+            subpath: None,
+            verify: false,
+            no_delete: None,
+            move_aside_blockers: false,
+        }
+    }
+}
+
+const SYSTEM_FILE_NAMES: &[&str] = &[".DS_Store", "Thumbs.db", "desktop.ini"];
+
+fn is_system_path(path: &str) -> bool {
+    let normalized = path.replace('\\', "/");
+    normalized == SHIELD_DIR
+        || normalized.starts_with(&format!("{}/", SHIELD_DIR))
+        || normalized
+            .rsplit('/')
+            .next()
+            .is_some_and(|name| SYSTEM_FILE_NAMES.contains(&name))
+}
+
+// Matches on whole path components, so "src" covers "src/main.rs" but not "srcgen/main.rs"
+fn is_in_subpath(path: &str, subpath: &str) -> bool {
+    let subpath = subpath.replace('\\', "/");
+    let subpath = subpath.trim_matches('/');
+    if subpath.is_empty() {
+        return true;
+    }
+    let path = path.replace('\\', "/");
+    path == subpath || path.starts_with(&format!("{}/", subpath))
+}
+
+impl RestoreOptions {
+    fn is_excluded(&self, path: &str) -> bool {
+        if self.skip_system && is_system_path(path) {
+            return true;
+        }
+        if self.subpath.as_deref().is_some_and(|subpath| !is_in_subpath(path, subpath)) {
+            return true;
+        }
+        let match_options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        self.exclude.iter().any(|entry| {
+            entry == path
+                || glob::Pattern::new(entry).is_ok_and(|pattern| pattern.matches_with(path, match_options))
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffStatus {
+    Unchanged,
+    Modified,
+    MissingOnDisk,
+    OnlyOnDisk,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub path: String,
+    pub status: DiffStatus,
+    pub snapshot_size: Option<u64>,
+    pub current_size: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ShieldStatus {
     pub running: bool,
     pub pid: Option<u32>,
+    #[serde(default)]
+    pub orphaned: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ShieldLogLine {
+    pub workspace_path: String,
+    pub line: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ShieldStatusChange {
+    pub workspace_path: String,
+    pub running: bool,
+    pub pid: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AutoCleanSummary {
+    pub workspace_path: String,
+    pub removed: usize,
+    pub freed_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OperationLogEntry {
+    pub action: String,
+    pub timestamp: i64,
+    pub success: bool,
+    #[serde(default)]
+    pub counts: std::collections::BTreeMap<String, u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -74,6 +442,130 @@ pub struct CommandResult {
     pub message: String,
 }
 
+#[derive(Default)]
+pub struct OperationRegistry {
+    operations: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    counter: AtomicU64,
+}
+
+impl OperationRegistry {
+    fn begin(&self) -> String {
+        let op_id = format!(
+            "op_{}_{}",
+            chrono::Utc::now().timestamp_millis(),
+            self.counter.fetch_add(1, Ordering::Relaxed)
+        );
+        self.operations
+            .lock()
+            .unwrap()
+            .insert(op_id.clone(), Arc::new(AtomicBool::new(false)));
+        op_id
+    }
+    
+    fn flag(&self, op_id: Option<&str>) -> Arc<AtomicBool> {
+        let Some(op_id) = op_id else {
+            return Arc::new(AtomicBool::new(false));
+        };
+        self.operations
+            .lock()
+            .unwrap()
+            .entry(op_id.to_string())
+            .or_default()
+            .clone()
+    }
+    
+    fn cancel(&self, op_id: &str) -> bool {
+        match self.operations.lock().unwrap().get(op_id) {
+            Some(flag) => {
+                flag.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+    
+    fn finish(&self, op_id: &str) {
+        self.operations.lock().unwrap().remove(op_id);
+    }
+}
+
+#[derive(Default)]
+pub struct ShieldBinaryCache {
+    path: Mutex<Option<PathBuf>>,
+}
+
+#[derive(Default)]
+pub struct IndexWatchers {
+    watchers: Mutex<HashMap<String, notify::RecommendedWatcher>>,
+}
+
+/// PIDs of `shield` CLI invocations the app is running right now. They match the orphan scan's
+/// "shield + workspace path" heuristic, so they are excluded from it.
+#[derive(Default)]
+pub struct LaunchedPids {
+    pids: Mutex<std::collections::HashSet<u32>>,
+}
+
+impl LaunchedPids {
+    fn track(&self, pid: u32) {
+        self.pids.lock().unwrap().insert(pid);
+    }
+    
+    fn untrack(&self, pid: u32) {
+        self.pids.lock().unwrap().remove(&pid);
+    }
+    
+    fn contains(&self, pid: u32) -> bool {
+        self.pids.lock().unwrap().contains(&pid)
+    }
+    
+    /// Same as `Command::output`, but the child is tracked for as long as it runs.
+    fn output(&self, command: &mut Command) -> std::io::Result<std::process::Output> {
+        let child = command
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()?;
+        let pid = child.id();
+        self.track(pid);
+        let output = child.wait_with_output();
+        self.untrack(pid);
+        output
+    }
+    
+    /// Like `output`, but kills the child and returns `None` once `timeout_ms` elapses.
+    fn output_within(&self, command: &mut Command, timeout_ms: u64) -> std::io::Result<Option<std::process::Output>> {
+        let mut child = command
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()?;
+        let pid = child.id();
+        self.track(pid);
+        let deadline = std::time::Instant::now() + std::time::Duration::from_millis(timeout_ms);
+        let exited = loop {
+            match child.try_wait() {
+                Ok(Some(_)) => break true,
+                Ok(None) if std::time::Instant::now() < deadline => {
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                }
+                _ => break false,
+            }
+        };
+        if !exited {
+            child.kill().ok();
+        }
+        let output = child.wait_with_output();
+        self.untrack(pid);
+        output.map(|output| exited.then_some(output))
+    }
+}
+
+#[derive(Default)]
+pub struct LogStreams {
+    watchers: Mutex<HashMap<String, notify::RecommendedWatcher>>,
+}
+
 fn get_global_config_path() -> PathBuf {
     let home = dirs::home_dir().expect("Could not find home directory");
     home.join(SHIELD_DIR).join(CONFIG_FILE)
@@ -107,16 +599,135 @@ fn save_global_config(config: &GlobalConfig) -> Result<(), String> {
     Ok(())
 }
 
+fn canonicalize_path(path: &str) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
+fn get_shield_dir(workspace_path: &str) -> PathBuf {
+    let local = PathBuf::from(workspace_path).join(SHIELD_DIR);
+    // A plain `.shield` file is a redirect holding the path of a relocated store
+    if local.is_file() {
+        if let Ok(target) = fs::read_to_string(&local) {
+            let target = target.trim();
+            if !target.is_empty() {
+                return PathBuf::from(target);
+            }
+        }
+    }
+    local
+}
+
+impl WorkspaceLayout {
+    // Every entry must stay a plain name inside the shield dir; purge and orphan cleanup delete
+    // under these paths, so `..`, absolute paths or nested escapes fall back to the default
+    fn sanitized(self) -> Self {
+        let defaults = WorkspaceLayout::default();
+        let keep = |value: String, default: String| {
+            let mut components = Path::new(&value).components();
+            match (components.next(), components.next()) {
+                (Some(std::path::Component::Normal(_)), None) => value,
+                _ => default,
+            }
+        };
+        WorkspaceLayout {
+            snapshots_dir: keep(self.snapshots_dir, defaults.snapshots_dir),
+            index_file: keep(self.index_file, defaults.index_file),
+            pid_file: keep(self.pid_file, defaults.pid_file),
+        }
+    }
+}
+
+type LayoutCacheEntry = (Option<std::time::SystemTime>, WorkspaceLayout);
+
+// Path helpers run many times per command, so config.json is only re-parsed when its mtime changes
+fn layout_cache() -> &'static Mutex<HashMap<PathBuf, LayoutCacheEntry>> {
+    static CACHE: std::sync::OnceLock<Mutex<HashMap<PathBuf, LayoutCacheEntry>>> = std::sync::OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Optional `.shield/config.json` overriding file names for daemons set up with a custom layout
+fn load_workspace_layout(workspace_path: &str) -> WorkspaceLayout {
+    let config_path = get_shield_dir(workspace_path).join(CONFIG_FILE);
+    let modified = fs::metadata(&config_path).and_then(|m| m.modified()).ok();
+    let mut cache = layout_cache().lock().unwrap();
+    if let Some((cached_mtime, layout)) = cache.get(&config_path) {
+        if *cached_mtime == modified {
+            return layout.clone();
+        }
+    }
+    let layout = fs::read_to_string(&config_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<WorkspaceLayout>(&content).ok())
+        .unwrap_or_default()
+        .sanitized();
+    cache.insert(config_path, (modified, layout.clone()));
+    layout
+}
+
 fn get_workspace_index_path(workspace_path: &str) -> PathBuf {
-    PathBuf::from(workspace_path).join(SHIELD_DIR).join(INDEX_FILE)
+    get_shield_dir(workspace_path).join(load_workspace_layout(workspace_path).index_file)
 }
 
 fn get_workspace_snapshots_dir(workspace_path: &str) -> PathBuf {
-    PathBuf::from(workspace_path).join(SHIELD_DIR).join(SNAPSHOTS_DIR)
+    get_shield_dir(workspace_path).join(load_workspace_layout(workspace_path).snapshots_dir)
 }
 
 fn get_pid_file_path(workspace_path: &str) -> PathBuf {
-    PathBuf::from(workspace_path).join(SHIELD_DIR).join(PID_FILE)
+    get_shield_dir(workspace_path).join(load_workspace_layout(workspace_path).pid_file)
+}
+
+fn get_log_file_path(workspace_path: &str) -> PathBuf {
+    get_shield_dir(workspace_path).join(LOG_FILE)
+}
+
+#[tauri::command]
+fn get_workspace_paths(workspace_path: String) -> WorkspacePaths {
+    let shield_dir = get_shield_dir(&workspace_path);
+    let layout = load_workspace_layout(&workspace_path);
+    let index_path = shield_dir.join(layout.index_file);
+    let snapshots_dir = shield_dir.join(layout.snapshots_dir);
+    let pid_file = shield_dir.join(layout.pid_file);
+    WorkspacePaths {
+        shield_dir_exists: shield_dir.is_dir(),
+        shield_dir: shield_dir.to_string_lossy().to_string(),
+        index_exists: index_path.is_file(),
+        index_path: index_path.to_string_lossy().to_string(),
+        snapshots_dir_exists: snapshots_dir.is_dir(),
+        snapshots_dir: snapshots_dir.to_string_lossy().to_string(),
+        pid_file_exists: pid_file.is_file(),
+        pid_file: pid_file.to_string_lossy().to_string(),
+    }
+}
+
+fn log_operation(workspace_path: &str, action: &str, success: bool, counts: &[(&str, u64)], detail: Option<String>) {
+    use std::io::Write;
+    
+    let entry = OperationLogEntry {
+        action: action.to_string(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        success,
+        counts: counts.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+        detail,
+    };
+    let Ok(line) = serde_json::to_string(&entry) else {
+        return;
+    };
+    let log_path = get_shield_dir(workspace_path).join(OPERATIONS_LOG_FILE);
+    if let Ok(mut file) = fs::OpenOptions::new().create(true).append(true).open(&log_path) {
+        writeln!(file, "{}", line).ok();
+    }
+}
+
+#[tauri::command]
+fn get_operation_log(workspace_path: String, limit: usize) -> Vec<OperationLogEntry> {
+    let log_path = get_shield_dir(&workspace_path).join(OPERATIONS_LOG_FILE);
+    let content = fs::read_to_string(&log_path).unwrap_or_default();
+    content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect()
 }
 
 fn is_process_running(pid: u32) -> bool {
@@ -149,6 +760,36 @@ fn is_process_running(pid: u32) -> bool {
     }
 }
 
+fn kill_process(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        unsafe {
+            libc::kill(pid as i32, libc::SIGKILL) == 0
+        }
+    }
+    #[cfg(windows)]
+    {
+        unsafe {
+            let handle = winapi::um::processthreadsapi::OpenProcess(
+                winapi::um::winnt::PROCESS_TERMINATE,
+                0,
+                pid,
+            );
+            if handle.is_null() {
+                false
+            } else {
+                let killed = winapi::um::processthreadsapi::TerminateProcess(handle, 1) != 0;
+                winapi::um::handleapi::CloseHandle(handle);
+                killed
+            }
+        }
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        false
+    }
+}
+
 fn check_shield_running(workspace_path: &str) -> ShieldStatus {
     let pid_file = get_pid_file_path(workspace_path);
     
@@ -156,6 +797,7 @@ fn check_shield_running(workspace_path: &str) -> ShieldStatus {
         return ShieldStatus {
             running: false,
             pid: None,
+            orphaned: false,
         };
     }
     
@@ -165,6 +807,7 @@ fn check_shield_running(workspace_path: &str) -> ShieldStatus {
                 return ShieldStatus {
                     running: true,
                     pid: Some(pid),
+                    orphaned: false,
                 };
             }
         }
@@ -173,6 +816,86 @@ fn check_shield_running(workspace_path: &str) -> ShieldStatus {
     ShieldStatus {
         running: false,
         pid: None,
+        orphaned: false,
+    }
+}
+
+fn list_processes() -> Vec<(u32, String)> {
+    #[cfg(unix)]
+    let output = Command::new("ps").args(["-axo", "pid=,args="]).output();
+    #[cfg(windows)]
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_Process | ForEach-Object { \"$($_.ProcessId) $($_.CommandLine)\" }",
+        ])
+        .output();
+    #[cfg(not(any(unix, windows)))]
+    let output: std::io::Result<std::process::Output> =
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "unsupported platform"));
+    
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        _ => return vec![],
+    };
+    
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (pid, cmdline) = line.split_once(char::is_whitespace)?;
+            Some((pid.parse().ok()?, cmdline.trim().to_string()))
+        })
+        .collect()
+}
+
+// Only the daemon's own signature counts; an editor, shell or `tail` opened on a path under the
+// workspace must never be mistaken for it, since callers may write its PID to shield.pid or kill it
+fn is_shield_daemon_for(cmdline: &str, workspace_path: &str) -> bool {
+    let is_daemon = cmdline
+        .split_once(" watch ")
+        .is_some_and(|(_, rest)| rest.trim_start().starts_with("--daemon"));
+    is_daemon
+        && daemon_watch_root_from_cmdline(cmdline)
+            .is_some_and(|root| canonicalize_path(&root) == canonicalize_path(workspace_path))
+}
+
+fn find_orphaned_shield_pid(launched: &LaunchedPids, processes: &[(u32, String)], workspace_path: &str) -> Option<u32> {
+    let own_pid = std::process::id();
+    processes
+        .iter()
+        .find(|(pid, cmdline)| {
+            *pid != own_pid
+                && !launched.contains(*pid)
+                && is_shield_daemon_for(cmdline, workspace_path)
+        })
+        .map(|(pid, _)| *pid)
+}
+
+fn detect_shield_status(launched: &LaunchedPids, workspace_path: &str) -> ShieldStatus {
+    detect_shield_status_with(launched, workspace_path, &mut None)
+}
+
+// `processes` is listed on first need and reused, so batch callers pay for one process scan at most
+fn detect_shield_status_with(
+    launched: &LaunchedPids,
+    workspace_path: &str,
+    processes: &mut Option<Vec<(u32, String)>>,
+) -> ShieldStatus {
+    let status = check_shield_running(workspace_path);
+    if status.running {
+        return status;
+    }
+    
+    let processes = processes.get_or_insert_with(list_processes);
+    match find_orphaned_shield_pid(launched, processes, workspace_path) {
+        Some(pid) => ShieldStatus {
+            running: true,
+            pid: Some(pid),
+            orphaned: true,
+        },
+        None => status,
     }
 }
 
@@ -208,59 +931,364 @@ fn find_bundled_shield_binary() -> Option<PathBuf> {
     None
 }
 
+fn is_executable(path: &Path) -> bool {
+    let Ok(meta) = fs::metadata(path) else {
+        return false;
+    };
+    if !meta.is_file() {
+        return false;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        meta.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(windows)]
+    {
+        true
+    }
+}
+
+fn search_path_for_shield() -> Option<PathBuf> {
+    if let Ok(path) = which::which("shield") {
+        return Some(path);
+    }
+    
+    if let Ok(path_env) = std::env::var("PATH") {
+        for path in std::env::split_paths(&path_env) {
+            let shield_path = path.join("shield");
+            if is_executable(&shield_path) {
+                return Some(shield_path);
+            }
+        }
+    }
+    
+    None
+}
+
 fn find_shield_binary() -> Option<PathBuf> {
     if let Some(bundled) = find_bundled_shield_binary() {
         return Some(bundled);
     }
     
-    if let Ok(path) = which::which("shield") {
+    // PATH entries on unreachable network mounts can block for a long time, so bound the search
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        tx.send(search_path_for_shield()).ok();
+    });
+    if let Ok(Some(path)) = rx.recv_timeout(std::time::Duration::from_millis(SHIELD_LOOKUP_TIMEOUT_MS)) {
         return Some(path);
     }
     
     if let Ok(home) = std::env::var("HOME") {
         let npm_global = PathBuf::from(&home).join(".npm-global/bin/shield");
-        if npm_global.exists() {
+        if is_executable(&npm_global) {
             return Some(npm_global);
         }
         
         let cargo_bin = PathBuf::from(&home).join(".cargo/bin/shield");
-        if cargo_bin.exists() {
+        if is_executable(&cargo_bin) {
             return Some(cargo_bin);
         }
     }
     
-    if let Ok(path_env) = std::env::var("PATH") {
-        for path in std::env::split_paths(&path_env) {
-            let shield_path = path.join("shield");
-            if shield_path.exists() {
-                return Some(shield_path);
-            }
+    None
+}
+
+fn resolve_shield_binary(cache: &ShieldBinaryCache) -> Option<PathBuf> {
+    let mut cached = cache.path.lock().unwrap();
+    if let Some(path) = cached.as_ref() {
+        if is_executable(path) {
+            return Some(path.clone());
         }
     }
     
-    None
+    *cached = find_shield_binary();
+    cached.clone()
+}
+
+// Stream from a buffered reader so large indexes are never held as one String alongside the parsed value
+fn read_index_as<T: serde::de::DeserializeOwned>(workspace_path: &str) -> Option<T> {
+    let file = fs::File::open(get_workspace_index_path(workspace_path)).ok()?;
+    serde_json::from_reader(std::io::BufReader::new(file)).ok()
+}
+
+// Returns the index with repaired ids, and whether any id had to be repaired
+fn read_workspace_index(workspace_path: &str) -> (BackupIndex, bool) {
+    if let Some(mut index) = read_index_as::<BackupIndex>(workspace_path) {
+        for snapshot in &mut index.snapshots {
+            if snapshot.files.is_empty() {
+                snapshot.files = vec![];
+            }
+        }
+        let repaired = !repair_snapshot_ids(&mut index.snapshots).is_empty();
+        return (index, repaired);
+    }
+    let index = BackupIndex {
+        version: SUPPORTED_INDEX_VERSION,
+        snapshots: vec![],
+    };
+    (index, false)
 }
 
 fn load_workspace_index(workspace_path: &str) -> BackupIndex {
+    let (index, repaired) = read_workspace_index(workspace_path);
+    if repaired {
+        persist_repaired_ids(workspace_path);
+    }
+    index
+}
+
+// Write repaired ids back under the lock so the CLI resolves snapshots by the same ids as the app.
+// Best effort: readers still get consistent ids in memory if the write fails.
+fn persist_repaired_ids(workspace_path: &str) {
+    update_workspace_index(workspace_path, |_| Ok(())).ok();
+}
+
+trait SnapshotIdentity {
+    fn id(&self) -> &str;
+    fn timestamp(&self) -> i64;
+    fn set_id(&mut self, id: String);
+}
+
+impl SnapshotIdentity for Snapshot {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+    
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+}
+
+impl SnapshotIdentity for SnapshotSummary {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+    
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+}
+
+// Lookups match on `id`, so a blank or repeated id would silently resolve to the wrong snapshot.
+// Regenerate them from the timestamp; callers persist the fix under the index lock.
+fn repair_snapshot_ids<T: SnapshotIdentity>(snapshots: &mut [T]) -> Vec<String> {
+    let mut seen: std::collections::HashSet<String> = std::collections::HashSet::new();
+    let mut needs_repair = vec![];
+    for (i, snapshot) in snapshots.iter().enumerate() {
+        if snapshot.id().trim().is_empty() || !seen.insert(snapshot.id().to_string()) {
+            needs_repair.push(i);
+        }
+    }
+    
+    let mut repairs = vec![];
+    for i in needs_repair {
+        let snapshot = &mut snapshots[i];
+        let base = format!("snap_{}", snapshot.timestamp());
+        let mut id = base.clone();
+        let mut suffix = 1;
+        while seen.contains(&id) {
+            id = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        seen.insert(id.clone());
+        repairs.push(if snapshot.id().trim().is_empty() {
+            format!("Blank snapshot id at {} reassigned to {}", snapshot.timestamp(), id)
+        } else {
+            format!("Duplicate snapshot id {} at {} reassigned to {}", snapshot.id(), snapshot.timestamp(), id)
+        });
+        snapshot.set_id(id);
+    }
+    repairs
+}
+
+fn read_raw_index(workspace_path: &str) -> Result<Option<serde_json::Value>, String> {
     let index_path = get_workspace_index_path(workspace_path);
-    if index_path.exists() {
-        if let Ok(content) = fs::read_to_string(&index_path) {
-            if let Ok(mut index) = serde_json::from_str::<BackupIndex>(&content) {
-                for snapshot in &mut index.snapshots {
-                    if snapshot.files.is_empty() {
-                        snapshot.files = vec![];
+    if !index_path.exists() {
+        return Ok(None);
+    }
+    let file = fs::File::open(&index_path).map_err(|e| e.to_string())?;
+    serde_json::from_reader(std::io::BufReader::new(file))
+        .map(Some)
+        .map_err(|e| format!("Index is not valid JSON: {}", e))
+}
+
+fn raw_index_version(raw: &serde_json::Value) -> i32 {
+    // The daemon has always treated a missing version as the v2 layout
+    raw["version"].as_i64().map(|v| v as i32).unwrap_or(SUPPORTED_INDEX_VERSION)
+}
+
+fn newer_index_error(version: i32) -> String {
+    format!(
+        "Index version {} is newer than this app supports ({}). Please update AgentShield Desktop.",
+        version, SUPPORTED_INDEX_VERSION
+    )
+}
+
+#[derive(Deserialize)]
+struct IndexVersionOnly {
+    version: Option<i32>,
+}
+
+// A newer daemon's index may not parse as `BackupIndex` at all, in which case `load_workspace_index`
+// falls back to an empty v2 index; writing or deleting on top of that would destroy the real history.
+fn ensure_index_version_supported(workspace_path: &str) -> Result<(), String> {
+    let version = read_index_as::<IndexVersionOnly>(workspace_path)
+        .and_then(|index| index.version)
+        .unwrap_or(SUPPORTED_INDEX_VERSION);
+    if version > SUPPORTED_INDEX_VERSION {
+        return Err(newer_index_error(version));
+    }
+    Ok(())
+}
+
+// Checks an index that was already loaded, so a write path parses index.json only once. An empty
+// result may be the fallback for an index that did not parse, so only then is the file's version read.
+fn ensure_loaded_index_supported(workspace_path: &str, index: &BackupIndex) -> Result<(), String> {
+    if index.version > SUPPORTED_INDEX_VERSION {
+        return Err(newer_index_error(index.version));
+    }
+    if index.snapshots.is_empty() {
+        ensure_index_version_supported(workspace_path)?;
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct SnapshotCountIndex {
+    #[serde(default)]
+    snapshots: Vec<serde::de::IgnoredAny>,
+}
+
+fn count_workspace_snapshots(workspace_path: &str) -> usize {
+    read_index_as::<SnapshotCountIndex>(workspace_path)
+        .map(|index| index.snapshots.len())
+        .unwrap_or(0)
+}
+
+// Only the fields needed for timeline queries; each snapshot's `files` array is skipped without being built
+#[derive(Deserialize)]
+struct SnapshotSummary {
+    id: String,
+    timestamp: i64,
+}
+
+#[derive(Deserialize)]
+struct SnapshotSummaryIndex {
+    #[serde(default)]
+    snapshots: Vec<SnapshotSummary>,
+}
+
+fn load_snapshot_summaries(workspace_path: &str) -> Vec<SnapshotSummary> {
+    let mut summaries = read_index_as::<SnapshotSummaryIndex>(workspace_path)
+        .map(|index| index.snapshots)
+        .unwrap_or_default();
+    // Same repair pass as the full index, so both resolve to identical ids
+    if !repair_snapshot_ids(&mut summaries).is_empty() {
+        persist_repaired_ids(workspace_path);
+    }
+    summaries
+}
+
+/// Advisory lock around read-modify-write cycles of `index.json`, shared with the shield daemon.
+///
+/// Protocol: a writer creates `.shield/index.lock` exclusively, containing
+/// `{"pid": <holder pid>, "timestamp": <ms since epoch>}`, re-reads `index.json`, writes it back,
+/// then deletes the lock. A lock whose holder is no longer running, or that is older than 30
+/// seconds, is stale and may be removed by the next writer. Writers give up after 5 seconds.
+struct IndexLock {
+    path: PathBuf,
+}
+
+impl IndexLock {
+    fn acquire(workspace_path: &str) -> Result<IndexLock, String> {
+        let path = get_shield_dir(workspace_path).join(INDEX_LOCK_FILE);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let started = std::time::Instant::now();
+        
+        loop {
+            match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    use std::io::Write;
+                    let holder = serde_json::json!({
+                        "pid": std::process::id(),
+                        "timestamp": chrono::Utc::now().timestamp_millis(),
+                    });
+                    file.write_all(holder.to_string().as_bytes()).ok();
+                    return Ok(IndexLock { path });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    if is_index_lock_stale(&path) {
+                        fs::remove_file(&path).ok();
+                        continue;
+                    }
+                    if started.elapsed().as_millis() > INDEX_LOCK_TIMEOUT_MS {
+                        return Err("Timed out waiting for the index lock; shield may be writing a snapshot".to_string());
                     }
+                    std::thread::sleep(std::time::Duration::from_millis(50));
                 }
-                return index;
+                Err(e) => return Err(format!("Failed to acquire index lock: {}", e)),
             }
         }
     }
-    BackupIndex {
-        version: 2,
-        snapshots: vec![],
+}
+
+impl Drop for IndexLock {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+fn is_index_lock_stale(path: &Path) -> bool {
+    let now = chrono::Utc::now().timestamp_millis();
+    let holder = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok());
+    
+    match holder {
+        Some(holder) => {
+            let timestamp = holder["timestamp"].as_i64().unwrap_or(0);
+            let pid_alive = holder["pid"]
+                .as_u64()
+                .map(|pid| is_process_running(pid as u32))
+                .unwrap_or(false);
+            !pid_alive || now - timestamp > INDEX_LOCK_STALE_MS
+        }
+        // The holder may still be writing its details, so fall back to the file age
+        None => fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map(|age| age.as_millis() as i64 > INDEX_LOCK_STALE_MS)
+            .unwrap_or(true),
     }
 }
 
+/// Loads the index under the index lock, applies `update`, and saves the result.
+fn update_workspace_index<T>(
+    workspace_path: &str,
+    update: impl FnOnce(&mut BackupIndex) -> Result<T, String>,
+) -> Result<T, String> {
+    let _lock = IndexLock::acquire(workspace_path)?;
+    let (mut index, _) = read_workspace_index(workspace_path);
+    ensure_loaded_index_supported(workspace_path, &index)?;
+    let result = update(&mut index)?;
+    save_workspace_index(workspace_path, &index)?;
+    Ok(result)
+}
+
+// Callers check the version before building `index`; see `ensure_loaded_index_supported`
 fn save_workspace_index(workspace_path: &str, index: &BackupIndex) -> Result<(), String> {
     let index_path = get_workspace_index_path(workspace_path);
     let content = serde_json::to_string_pretty(index).map_err(|e| e.to_string())?;
@@ -268,6 +1296,40 @@ fn save_workspace_index(workspace_path: &str, index: &BackupIndex) -> Result<(),
     Ok(())
 }
 
+fn relative_time(ts: i64, now: i64) -> String {
+    let seconds = (now - ts).max(0) / 1000;
+    let (value, unit) = match seconds {
+        0..=59 => return "just now".to_string(),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86_399 => (seconds / 3600, "hour"),
+        86_400..=2_591_999 => (seconds / 86_400, "day"),
+        2_592_000..=31_535_999 => (seconds / 2_592_000, "month"),
+        _ => (seconds / 31_536_000, "year"),
+    };
+    format!("{} {}{} ago", value, unit, if value == 1 { "" } else { "s" })
+}
+
+#[tauri::command]
+fn format_relative_time(ts: i64) -> String {
+    relative_time(ts, chrono::Utc::now().timestamp_millis())
+}
+
+#[tauri::command]
+fn format_bytes(n: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    
+    let mut value = n as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    // Same rounding as the frontend's formatBytes: two decimals, trailing zeros dropped
+    let rounded = format!("{:.2}", value);
+    let trimmed = rounded.trim_end_matches('0').trim_end_matches('.');
+    format!("{} {}", trimmed, UNITS[unit])
+}
+
 #[tauri::command]
 fn get_workspaces() -> Vec<Workspace> {
     let config = load_global_config();
@@ -275,7 +1337,19 @@ fn get_workspaces() -> Vec<Workspace> {
 }
 
 #[tauri::command]
-fn add_workspace(path: String) -> Result<Workspace, String> {
+fn get_workspaces_by_activity() -> Vec<Workspace> {
+    let mut workspaces: Vec<(Option<i64>, Workspace)> = load_global_config()
+        .workspaces
+        .into_iter()
+        .map(|w| (get_last_activity(w.path.clone()), w))
+        .collect();
+    // `None` sorts below any timestamp, so workspaces without snapshots end up last
+    workspaces.sort_by_key(|(last_activity, _)| std::cmp::Reverse(*last_activity));
+    workspaces.into_iter().map(|(_, w)| w).collect()
+}
+
+#[tauri::command]
+fn add_workspace(path: String, force: Option<bool>) -> Result<Workspace, String> {
     let path_buf = PathBuf::from(&path);
     
     if !path_buf.exists() {
@@ -286,11 +1360,16 @@ fn add_workspace(path: String) -> Result<Workspace, String> {
         return Err("Path is not a directory".to_string());
     }
     
-    let name = path_buf
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("Unknown")
-        .to_string();
+    if !force.unwrap_or(false) {
+        if let Some(reason) = dangerous_workspace_reason(&canonicalize_path(&path)) {
+            return Err(format!(
+                "Refusing to protect {}: shield would watch and back up a huge tree. Pass force to add it anyway.",
+                reason
+            ));
+        }
+    }
+    
+    let name = workspace_name_for(&path_buf);
     
     let mut config = load_global_config();
     
@@ -298,18 +1377,124 @@ fn add_workspace(path: String) -> Result<Workspace, String> {
         return Err("Workspace already exists".to_string());
     }
     
+    let canonical = canonicalize_path(&path);
+    for existing in &config.workspaces {
+        let existing_canonical = canonicalize_path(&existing.path);
+        if canonical == existing_canonical {
+            return Err("Workspace already exists".to_string());
+        }
+        if canonical.starts_with(&existing_canonical) {
+            return Err(format!(
+                "Directory is inside existing workspace '{}' ({})",
+                existing.name, existing.path
+            ));
+        }
+        if existing_canonical.starts_with(&canonical) {
+            return Err(format!(
+                "Directory contains existing workspace '{}' ({})",
+                existing.name, existing.path
+            ));
+        }
+    }
+    
     let workspace = Workspace {
         path: path.clone(),
         name,
         added_at: chrono::Utc::now().timestamp_millis(),
+        color: None,
+        icon: None,
+        max_storage_bytes: None,
+        auto_clean_days: None,
+        last_restore: None,
+        env: HashMap::new(),
     };
     
+    initialize_shield_dir(&path)?;
     config.workspaces.push(workspace.clone());
     save_global_config(&config)?;
     
     Ok(workspace)
 }
 
+// Lay out the vault the way the daemon would, so stats and history queries work before it first runs.
+// An existing index is left alone so re-adding a workspace keeps its history.
+fn initialize_shield_dir(workspace_path: &str) -> Result<(), String> {
+    fs::create_dir_all(get_workspace_snapshots_dir(workspace_path))
+        .map_err(|e| format!("Failed to create {}: {}", SHIELD_DIR, e))?;
+    if !get_workspace_index_path(workspace_path).exists() {
+        save_workspace_index(
+            workspace_path,
+            &BackupIndex {
+                version: SUPPORTED_INDEX_VERSION,
+                snapshots: vec![],
+            },
+        )?;
+    }
+    Ok(())
+}
+
+fn workspace_name_for(path: &Path) -> String {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("Unknown")
+        .to_string()
+}
+
+#[tauri::command]
+fn refresh_workspace_name(path: String) -> Result<Workspace, String> {
+    let path_buf = PathBuf::from(&path);
+    if !path_buf.is_dir() {
+        return Err("Directory does not exist".to_string());
+    }
+    
+    let mut config = load_global_config();
+    let workspace = config
+        .workspaces
+        .iter_mut()
+        .find(|w| w.path == path)
+        .ok_or("Workspace not found")?;
+    
+    // Resolve symlinks so a link left behind after moving the folder reports the real directory name
+    workspace.name = workspace_name_for(&canonicalize_path(&path));
+    let updated = workspace.clone();
+    save_global_config(&config)?;
+    
+    Ok(updated)
+}
+
+fn dangerous_workspace_reason(path: &Path) -> Option<String> {
+    if path.parent().is_none() {
+        return Some("the filesystem root".to_string());
+    }
+    if let Some(home) = dirs::home_dir() {
+        let home = fs::canonicalize(&home).unwrap_or(home);
+        if path == home {
+            return Some("your home directory".to_string());
+        }
+        if home.parent() == Some(path) {
+            return Some("the directory holding user homes".to_string());
+        }
+    }
+    
+    #[cfg(unix)]
+    const SYSTEM_DIRS: &[&str] = &[
+        "/bin", "/boot", "/dev", "/etc", "/lib", "/opt", "/proc", "/sbin", "/sys", "/tmp", "/usr", "/var",
+        "/Applications", "/Library", "/System", "/Volumes", "/private", "/private/tmp", "/private/var",
+    ];
+    #[cfg(windows)]
+    const SYSTEM_DIRS: &[&str] = &["C:\\Windows", "C:\\Program Files", "C:\\Program Files (x86)", "C:\\ProgramData"];
+    
+    SYSTEM_DIRS
+        .iter()
+        .any(|dir| canonicalize_path(dir) == path)
+        .then(|| format!("system directory {}", path.display()))
+}
+
+#[tauri::command]
+fn is_workspace_managed(path: String) -> bool {
+    get_workspace_index_path(&path).is_file()
+}
+
 #[tauri::command]
 fn remove_workspace(path: String) -> Result<(), String> {
     let mut config = load_global_config();
@@ -319,181 +1504,2960 @@ fn remove_workspace(path: String) -> Result<(), String> {
 }
 
 #[tauri::command]
-fn get_workspace_snapshots(workspace_path: String) -> Vec<Snapshot> {
+fn clone_workspace_config(source_path: String, target_path: String) -> Result<Workspace, String> {
+    let source = load_global_config()
+        .workspaces
+        .into_iter()
+        .find(|w| w.path == source_path)
+        .ok_or("Source workspace not found")?;
+    
+    // The layout has to be in place before the target's vault is initialized, so copy config.json first
+    let source_layout = get_shield_dir(&source_path).join(CONFIG_FILE);
+    let target_layout = get_shield_dir(&target_path).join(CONFIG_FILE);
+    let copied_layout = source_layout.is_file() && Path::new(&target_path).is_dir() && !target_layout.exists();
+    if copied_layout {
+        if let Some(parent) = target_layout.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::copy(&source_layout, &target_layout).map_err(|e| e.to_string())?;
+    }
+    
+    // Registers the target with the usual checks; its index starts empty since no history is copied
+    if let Err(e) = add_workspace(target_path.clone(), None) {
+        if copied_layout {
+            fs::remove_file(&target_layout).ok();
+            if let Some(parent) = target_layout.parent() {
+                fs::remove_dir(parent).ok();
+            }
+        }
+        return Err(e);
+    }
+    
+    let mut config = load_global_config();
+    let workspace = config
+        .workspaces
+        .iter_mut()
+        .find(|w| w.path == target_path)
+        .ok_or("Workspace not found")?;
+    workspace.color = source.color;
+    workspace.icon = source.icon;
+    workspace.max_storage_bytes = source.max_storage_bytes;
+    workspace.auto_clean_days = source.auto_clean_days;
+    workspace.env = source.env;
+    let updated = workspace.clone();
+    save_global_config(&config)?;
+    
+    Ok(updated)
+}
+
+#[tauri::command]
+fn rename_workspace_directory(old_path: String, new_name_or_path: String) -> Result<Workspace, String> {
+    let mut config = load_global_config();
+    let workspace = config
+        .workspaces
+        .iter_mut()
+        .find(|w| w.path == old_path)
+        .ok_or("Workspace not found")?;
+    
+    if check_shield_running(&old_path).running {
+        return Err("Stop shield before renaming the workspace directory".to_string());
+    }
+    
+    // A bare name renames in place; anything with a separator is taken as the full destination
+    let new_path = if new_name_or_path.contains(['/', '\\']) {
+        PathBuf::from(&new_name_or_path)
+    } else {
+        PathBuf::from(&old_path)
+            .parent()
+            .ok_or("Workspace has no parent directory")?
+            .join(&new_name_or_path)
+    };
+    if new_path.exists() {
+        return Err(format!("Destination already exists: {}", new_path.display()));
+    }
+    
+    fs::rename(&old_path, &new_path).map_err(|e| e.to_string())?;
+    
+    workspace.path = new_path.to_string_lossy().to_string();
+    workspace.name = workspace_name_for(&new_path);
+    let updated = workspace.clone();
+    save_global_config(&config)?;
+    
+    Ok(updated)
+}
+
+#[tauri::command]
+fn remove_workspaces(paths: Vec<String>) -> Result<usize, String> {
+    let mut config = load_global_config();
+    let before = config.workspaces.len();
+    config.workspaces.retain(|w| !paths.contains(&w.path));
+    let removed = before - config.workspaces.len();
+    
+    if removed > 0 {
+        save_global_config(&config)?;
+    }
+    
+    Ok(removed)
+}
+
+#[tauri::command]
+fn update_workspace_appearance(
+    path: String,
+    color: Option<String>,
+    icon: Option<String>,
+) -> Result<Workspace, String> {
+    let mut config = load_global_config();
+    let workspace = config
+        .workspaces
+        .iter_mut()
+        .find(|w| w.path == path)
+        .ok_or("Workspace not found")?;
+    
+    workspace.color = color;
+    workspace.icon = icon;
+    let updated = workspace.clone();
+    save_global_config(&config)?;
+    
+    Ok(updated)
+}
+
+#[tauri::command]
+fn set_storage_budget(path: String, max_storage_bytes: Option<u64>) -> Result<Workspace, String> {
+    let mut config = load_global_config();
+    let workspace = config
+        .workspaces
+        .iter_mut()
+        .find(|w| w.path == path)
+        .ok_or("Workspace not found")?;
+    
+    workspace.max_storage_bytes = max_storage_bytes;
+    let updated = workspace.clone();
+    save_global_config(&config)?;
+    
+    Ok(updated)
+}
+
+#[tauri::command]
+fn set_auto_clean_days(path: String, auto_clean_days: Option<i64>) -> Result<Workspace, String> {
+    if auto_clean_days.is_some_and(|days| days < 1) {
+        return Err("Auto-clean age must be at least one day".to_string());
+    }
+    
+    let mut config = load_global_config();
+    let workspace = config
+        .workspaces
+        .iter_mut()
+        .find(|w| w.path == path)
+        .ok_or("Workspace not found")?;
+    
+    workspace.auto_clean_days = auto_clean_days;
+    let updated = workspace.clone();
+    save_global_config(&config)?;
+    
+    Ok(updated)
+}
+
+#[tauri::command]
+fn get_effective_config(workspace_path: String) -> EffectiveConfig {
+    let config = load_global_config();
+    let stored = config.workspaces.iter().find(|w| w.path == workspace_path).cloned();
+    let layout = load_workspace_layout(&workspace_path);
+    let default_layout = WorkspaceLayout::default();
+    let storage_dir = get_shield_dir(&workspace_path);
+    
+    let mut overridden = vec![];
+    if config.watch_debounce_ms.is_some() {
+        overridden.push("watch_debounce_ms".to_string());
+    }
+    if config.restore_no_delete.is_some() {
+        overridden.push("restore_no_delete".to_string());
+    }
+    if let Some(workspace) = &stored {
+        if workspace.auto_clean_days.is_some() {
+            overridden.push("auto_clean_days".to_string());
+        }
+        if workspace.max_storage_bytes.is_some() {
+            overridden.push("max_storage_bytes".to_string());
+        }
+        if !workspace.env.is_empty() {
+            overridden.push("env".to_string());
+        }
+    }
+    if storage_dir != PathBuf::from(&workspace_path).join(SHIELD_DIR) {
+        overridden.push("storage_dir".to_string());
+    }
+    if layout.snapshots_dir != default_layout.snapshots_dir {
+        overridden.push("layout.snapshots_dir".to_string());
+    }
+    if layout.index_file != default_layout.index_file {
+        overridden.push("layout.index_file".to_string());
+    }
+    if layout.pid_file != default_layout.pid_file {
+        overridden.push("layout.pid_file".to_string());
+    }
+    
+    let effective = EffectiveSettings {
+        watch_debounce_ms: config.watch_debounce_ms.unwrap_or(DEFAULT_WATCH_DEBOUNCE_MS),
+        restore_no_delete: config.restore_no_delete.unwrap_or(false),
+        auto_clean_days: stored.as_ref().and_then(|w| w.auto_clean_days),
+        max_storage_bytes: stored.as_ref().and_then(|w| w.max_storage_bytes),
+        storage_dir: storage_dir.to_string_lossy().to_string(),
+        layout,
+        env: stored.as_ref().map(|w| w.env.clone()).unwrap_or_default(),
+    };
+    
+    EffectiveConfig {
+        stored,
+        effective,
+        overridden,
+    }
+}
+
+#[tauri::command]
+fn get_workspace_snapshots(workspace_path: String, trigger: Option<String>, subpath: Option<String>) -> Vec<Snapshot> {
     let index = load_workspace_index(&workspace_path);
     let mut snapshots = index.snapshots;
+    if let Some(trigger) = &trigger {
+        snapshots.retain(|s| s.trigger.as_deref() == Some(trigger.as_str()));
+    }
+    if let Some(subpath) = &subpath {
+        for snapshot in &mut snapshots {
+            snapshot.files.retain(|f| is_in_subpath(&f.path, subpath));
+        }
+        snapshots.retain(|s| !s.files.is_empty());
+    }
     snapshots.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
     snapshots
 }
 
+fn snapshot_matches_query(snapshot: &Snapshot, query: &str) -> bool {
+    let query = query.to_lowercase();
+    snapshot.files.iter().any(|f| {
+        f.path.to_lowercase().contains(&query)
+            || f.renamed_to.as_deref().is_some_and(|r| r.to_lowercase().contains(&query))
+    })
+}
+
 #[tauri::command]
-fn get_workspace_stats(workspace_path: String) -> WorkspaceStats {
-    let index = load_workspace_index(&workspace_path);
-    let mut unique_files = std::collections::HashSet::new();
-    let mut total_files = 0;
-    let mut total_size: u64 = 0;
+fn search_snapshots(workspace_path: String, query: String) -> Vec<Snapshot> {
+    let mut snapshots: Vec<Snapshot> = load_workspace_index(&workspace_path)
+        .snapshots
+        .into_iter()
+        .filter(|s| snapshot_matches_query(s, &query))
+        .collect();
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
+    snapshots
+}
+
+#[tauri::command]
+fn get_file_history(workspace_path: String, file_path: String) -> Vec<Snapshot> {
+    let mut snapshots: Vec<Snapshot> = load_workspace_index(&workspace_path)
+        .snapshots
+        .into_iter()
+        .filter(|s| {
+            s.files
+                .iter()
+                .any(|f| f.path == file_path || f.renamed_to.as_deref() == Some(file_path.as_str()))
+        })
+        .collect();
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
+    snapshots
+}
+
+#[tauri::command]
+fn latest_snapshot_for_file(workspace_path: String, file_path: String) -> Option<String> {
+    load_workspace_index(&workspace_path)
+        .snapshots
+        .into_iter()
+        .filter(|s| s.files.iter().any(|f| f.path == file_path))
+        .max_by_key(|s| s.timestamp)
+        .map(|s| s.id)
+}
+
+#[tauri::command]
+fn search_all_workspaces(query: String) -> Vec<(String, Snapshot)> {
+    let config = load_global_config();
+    let mut results = vec![];
     
-    for snapshot in &index.snapshots {
-        for file in &snapshot.files {
-            unique_files.insert(file.path.clone());
-            total_files += 1;
-            total_size += file.size;
+    for workspace in &config.workspaces {
+        if !Path::new(&workspace.path).exists() {
+            continue;
+        }
+        for snapshot in load_workspace_index(&workspace.path).snapshots {
+            if snapshot_matches_query(&snapshot, &query) {
+                results.push((workspace.path.clone(), snapshot));
+            }
+        }
+    }
+    
+    results.sort_by_key(|(_, s)| std::cmp::Reverse(s.timestamp));
+    results
+}
+
+#[tauri::command]
+fn compare_workspaces(a_path: String, b_path: String) -> WorkspaceComparison {
+    let latest_files = |workspace_path: &str| -> std::collections::BTreeMap<String, u64> {
+        load_workspace_index(workspace_path)
+            .snapshots
+            .into_iter()
+            .max_by_key(|s| s.timestamp)
+            .map(|s| s.files.into_iter().map(|f| (f.path, f.size)).collect())
+            .unwrap_or_default()
+    };
+    let a_files = latest_files(&a_path);
+    let b_files = latest_files(&b_path);
+    
+    let mut comparison = WorkspaceComparison::default();
+    for (path, a_size) in &a_files {
+        match b_files.get(path) {
+            None => comparison.only_in_a.push(path.clone()),
+            Some(b_size) if b_size != a_size => comparison.different_size.push(path.clone()),
+            Some(_) => {}
+        }
+    }
+    comparison.only_in_b = b_files.keys().filter(|p| !a_files.contains_key(*p)).cloned().collect();
+    
+    comparison
+}
+
+#[tauri::command]
+fn find_snapshot_workspace(snapshot_id: String) -> Option<String> {
+    load_global_config()
+        .workspaces
+        .into_iter()
+        .filter(|w| Path::new(&w.path).exists())
+        .find(|w| load_snapshot_summaries(&w.path).iter().any(|s| s.id == snapshot_id))
+        .map(|w| w.path)
+}
+
+#[tauri::command]
+fn get_coverage_gaps(workspace_path: String, threshold_minutes: i64) -> Vec<(i64, i64)> {
+    let mut timestamps: Vec<i64> = load_snapshot_summaries(&workspace_path)
+        .iter()
+        .map(|s| s.timestamp)
+        .collect();
+    timestamps.sort();
+    
+    let threshold_ms = threshold_minutes.max(0) * 60 * 1000;
+    timestamps
+        .windows(2)
+        .filter(|pair| pair[1] - pair[0] > threshold_ms)
+        .map(|pair| (pair[0], pair[1]))
+        .collect()
+}
+
+/// Groups snapshots into work sessions split wherever `get_coverage_gaps` would report a gap,
+/// newest session first to match the history list.
+#[tauri::command]
+fn get_snapshot_sessions(workspace_path: String, gap_minutes: i64) -> Vec<SnapshotSession> {
+    let mut summaries = load_snapshot_summaries(&workspace_path);
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
+    
+    let gap_ms = gap_minutes.max(0) * 60 * 1000;
+    let mut sessions: Vec<SnapshotSession> = vec![];
+    for summary in summaries {
+        match sessions.last_mut() {
+            Some(session) if session.start - summary.timestamp <= gap_ms => {
+                session.start = summary.timestamp;
+                session.snapshot_ids.push(summary.id);
+            }
+            _ => sessions.push(SnapshotSession {
+                start: summary.timestamp,
+                end: summary.timestamp,
+                snapshot_ids: vec![summary.id],
+            }),
+        }
+    }
+    sessions
+}
+
+#[tauri::command]
+fn get_adjacent_snapshots(workspace_path: String, snapshot_id: String) -> (Option<String>, Option<String>) {
+    let mut snapshots = load_snapshot_summaries(&workspace_path);
+    snapshots.sort_by_key(|s| s.timestamp);
+    
+    let Some(position) = snapshots.iter().position(|s| s.id == snapshot_id) else {
+        return (None, None);
+    };
+    let previous = position.checked_sub(1).map(|i| snapshots[i].id.clone());
+    let next = snapshots.get(position + 1).map(|s| s.id.clone());
+    (previous, next)
+}
+
+#[tauri::command]
+fn get_snapshot_changes(workspace_path: String, snapshot_id: String) -> Result<SnapshotChanges, String> {
+    let index = load_workspace_index(&workspace_path);
+    let snapshot = index
+        .snapshots
+        .into_iter()
+        .find(|s| s.id == snapshot_id)
+        .ok_or("Snapshot not found")?;
+    
+    let mut changes = SnapshotChanges::default();
+    for file in snapshot.files {
+        match file.event() {
+            Ok(EventType::Create) => changes.created.push(file),
+            Ok(EventType::Change) => changes.modified.push(file),
+            Ok(EventType::Delete) => changes.deleted.push(file),
+            Ok(EventType::Rename) => changes.renamed.push(file),
+            Err(_) => changes.unknown.push(file),
+        }
+    }
+    
+    Ok(changes)
+}
+
+#[tauri::command]
+fn get_snapshot_size(workspace_path: String, snapshot_id: String) -> Result<u64, String> {
+    let index = load_workspace_index(&workspace_path);
+    let snapshot = index
+        .snapshots
+        .iter()
+        .find(|s| s.id == snapshot_id)
+        .ok_or("Snapshot not found")?;
+    
+    Ok(snapshot.files.iter().map(|f| f.size).sum())
+}
+
+#[tauri::command]
+fn get_index_version(workspace_path: String) -> i32 {
+    match read_raw_index(&workspace_path) {
+        Ok(Some(raw)) => raw_index_version(&raw),
+        _ => SUPPORTED_INDEX_VERSION,
+    }
+}
+
+#[tauri::command]
+fn migrate_index(workspace_path: String) -> Result<i32, String> {
+    let _lock = IndexLock::acquire(&workspace_path)?;
+    let Some(mut raw) = read_raw_index(&workspace_path)? else {
+        return Ok(SUPPORTED_INDEX_VERSION);
+    };
+    
+    let version = raw_index_version(&raw);
+    if version > SUPPORTED_INDEX_VERSION {
+        return Err(newer_index_error(version));
+    }
+    
+    // Backfill fields that older writers could omit so the index parses under the current schema.
+    // A hand-edited or truncated index may hold anything, so entries that are not objects are dropped.
+    let root = raw
+        .as_object_mut()
+        .ok_or("Malformed index: the root is not a JSON object")?;
+    if !root.get("snapshots").is_some_and(|s| s.is_array()) {
+        root.insert("snapshots".to_string(), serde_json::json!([]));
+    }
+    if let Some(snapshots) = root.get_mut("snapshots").and_then(|s| s.as_array_mut()) {
+        snapshots.retain(|snapshot| snapshot.is_object());
+        for snapshot in snapshots.iter_mut().filter_map(|s| s.as_object_mut()) {
+            let timestamp = snapshot.get("timestamp").and_then(|t| t.as_i64()).unwrap_or(0);
+            snapshot.insert("timestamp".to_string(), serde_json::json!(timestamp));
+            if !snapshot.get("id").is_some_and(|id| id.is_string()) {
+                snapshot.insert("id".to_string(), serde_json::json!(format!("snap_{}", timestamp)));
+            }
+            if !snapshot.get("files").is_some_and(|f| f.is_array()) {
+                snapshot.insert("files".to_string(), serde_json::json!([]));
+            }
+            if let Some(files) = snapshot.get_mut("files").and_then(|f| f.as_array_mut()) {
+                files.retain(|file| file.is_object());
+                for file in files.iter_mut().filter_map(|f| f.as_object_mut()) {
+                    if !file.get("backupPath").is_some_and(|b| b.is_string()) {
+                        file.insert("backupPath".to_string(), serde_json::json!(""));
+                    }
+                    if !file.get("size").is_some_and(|s| s.is_u64()) {
+                        file.insert("size".to_string(), serde_json::json!(0));
+                    }
+                    if !file.get("eventType").is_some_and(|e| e.is_string()) {
+                        file.insert("eventType".to_string(), serde_json::json!("change"));
+                    }
+                }
+            }
+        }
+    }
+    root.insert("version".to_string(), serde_json::json!(SUPPORTED_INDEX_VERSION));
+    
+    let index: BackupIndex = serde_json::from_value(raw).map_err(|e| format!("Index could not be migrated: {}", e))?;
+    save_workspace_index(&workspace_path, &index)?;
+    
+    Ok(SUPPORTED_INDEX_VERSION)
+}
+
+#[tauri::command]
+fn get_workspace_stats(workspace_path: String, subpath: Option<String>) -> WorkspaceStats {
+    let index = load_workspace_index(&workspace_path);
+    let mut unique_files = std::collections::HashSet::new();
+    let mut total_files = 0;
+    let mut total_size: u64 = 0;
+    let mut snapshots = 0;
+    
+    for snapshot in &index.snapshots {
+        let mut touched = false;
+        for file in &snapshot.files {
+            if subpath.as_deref().is_some_and(|subpath| !is_in_subpath(&file.path, subpath)) {
+                continue;
+            }
+            touched = true;
+            unique_files.insert(file.path.clone());
+            total_files += 1;
+            total_size += file.size;
+        }
+        if touched || subpath.is_none() {
+            snapshots += 1;
         }
     }
     
     WorkspaceStats {
-        snapshots: index.snapshots.len(),
+        snapshots,
         total_files,
         total_size,
         unique_files: unique_files.len(),
     }
 }
 
-fn get_restore_lock_path(workspace_path: &str) -> PathBuf {
-    PathBuf::from(workspace_path).join(SHIELD_DIR).join("restore.lock")
+#[derive(Deserialize)]
+struct FilePathEntry {
+    path: String,
+}
+
+#[derive(Deserialize)]
+struct FilePathSnapshot {
+    #[serde(default)]
+    files: Vec<FilePathEntry>,
+}
+
+#[derive(Deserialize)]
+struct FilePathIndex {
+    #[serde(default)]
+    snapshots: Vec<FilePathSnapshot>,
+}
+
+#[tauri::command]
+fn get_unique_file_count(workspace_path: String) -> usize {
+    let Some(index) = read_index_as::<FilePathIndex>(&workspace_path) else {
+        return 0;
+    };
+    let unique_files: std::collections::HashSet<String> = index
+        .snapshots
+        .into_iter()
+        .flat_map(|s| s.files.into_iter().map(|f| f.path))
+        .collect();
+    unique_files.len()
+}
+
+#[tauri::command]
+fn get_snapshot_count(workspace_path: String) -> usize {
+    count_workspace_snapshots(&workspace_path)
+}
+
+#[tauri::command]
+fn get_last_activity(workspace_path: String) -> Option<i64> {
+    load_snapshot_summaries(&workspace_path).iter().map(|s| s.timestamp).max()
+}
+
+#[tauri::command]
+fn get_global_stats() -> GlobalStats {
+    let config = load_global_config();
+    let mut stats = GlobalStats {
+        workspaces: 0,
+        missing_workspaces: 0,
+        running: 0,
+        snapshots: 0,
+        total_size: 0,
+    };
+    
+    for workspace in &config.workspaces {
+        if !PathBuf::from(&workspace.path).is_dir() {
+            stats.missing_workspaces += 1;
+            continue;
+        }
+        
+        stats.workspaces += 1;
+        if check_shield_running(&workspace.path).running {
+            stats.running += 1;
+        }
+        
+        let index = load_workspace_index(&workspace.path);
+        stats.snapshots += index.snapshots.len();
+        for snapshot in &index.snapshots {
+            for file in &snapshot.files {
+                stats.total_size += file.size;
+            }
+        }
+    }
+    
+    stats
+}
+
+fn get_restore_lock_path(workspace_path: &str) -> PathBuf {
+    get_shield_dir(workspace_path).join("restore.lock")
+}
+
+fn hash_file(path: &Path) -> Option<String> {
+    use sha2::{Digest, Sha256};
+    
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+// Flattening separators into `__` lets `a/b__c` and `a__b/c` share one blob name,
+// so the name is keyed on a hash of the full path and keeps only the file name for readability
+fn backup_name_for(timestamp: i64, relative_path: &str) -> String {
+    use sha2::{Digest, Sha256};
+    
+    let normalized = relative_path.replace('\\', "/");
+    let digest = format!("{:x}", Sha256::digest(normalized.as_bytes()));
+    let file_name = normalized.rsplit('/').next().unwrap_or_default();
+    format!("{}_{}_{}", timestamp, &digest[..16], file_name)
+}
+
+fn open_backup(backup_full_path: &Path, codec: Option<&str>) -> std::io::Result<Box<dyn std::io::Read>> {
+    let reader = std::io::BufReader::new(fs::File::open(backup_full_path)?);
+    match codec {
+        None => Ok(Box::new(reader)),
+        Some("gzip") => Ok(Box::new(flate2::bufread::GzDecoder::new(reader))),
+        Some(other) => Err(std::io::Error::other(format!("unsupported backup codec: {}", other))),
+    }
+}
+
+fn target_matches_backup(backup_full_path: &Path, target_path: &Path, codec: Option<&str>) -> bool {
+    // Compressed blobs differ in size from the original, so compare their decoded content directly
+    if codec.is_some() {
+        return fs::metadata(target_path).is_ok_and(|m| m.is_file())
+            && backup_identical(backup_full_path, codec, target_path);
+    }
+    
+    let (Ok(backup_meta), Ok(target_meta)) = (fs::metadata(backup_full_path), fs::metadata(target_path)) else {
+        return false;
+    };
+    if !target_meta.is_file() || backup_meta.len() != target_meta.len() {
+        return false;
+    }
+    match (hash_file(backup_full_path), hash_file(target_path)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+enum CopyOutcome {
+    Restored,
+    Skipped,
+    Failed(String),
+}
+
+fn restore_from_backup(backup_full_path: &Path, target_path: &Path, codec: Option<&str>) -> CopyOutcome {
+    if !backup_full_path.exists() {
+        return CopyOutcome::Failed("backup missing".to_string());
+    }
+    
+    // Leave byte-identical files alone so re-running a restore doesn't touch mtimes
+    if target_matches_backup(backup_full_path, target_path, codec) {
+        return CopyOutcome::Skipped;
+    }
+    
+    if let Some(parent) = target_path.parent() {
+        fs::create_dir_all(parent).ok();
+    }
+    match with_io_retry(|| copy_backup_to(backup_full_path, codec, target_path)) {
+        Ok(_) => CopyOutcome::Restored,
+        Err(e) => CopyOutcome::Failed(e.to_string()),
+    }
+}
+
+// Network filesystems surface brief hiccups as these kinds; anything else is treated as a real failure
+fn is_transient_io_error(e: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+    matches!(
+        e.kind(),
+        ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::ResourceBusy
+    )
+}
+
+/// Runs `op`, retrying transient I/O errors with exponential backoff before giving up.
+fn with_io_retry<T>(mut op: impl FnMut() -> std::io::Result<T>) -> std::io::Result<T> {
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(e) if attempt < RESTORE_RETRY_ATTEMPTS && is_transient_io_error(&e) => {
+                std::thread::sleep(std::time::Duration::from_millis(RESTORE_RETRY_BASE_MS << (attempt - 1)));
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn read_backup(backup_full_path: &Path, codec: Option<&str>) -> std::io::Result<Vec<u8>> {
+    let mut bytes = vec![];
+    open_backup(backup_full_path, codec)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn copy_backup_to(backup_full_path: &Path, codec: Option<&str>, target_path: &Path) -> std::io::Result<u64> {
+    if codec.is_none() {
+        return fs::copy(backup_full_path, target_path);
+    }
+    let mut reader = open_backup(backup_full_path, codec)?;
+    let mut target = fs::File::create(target_path)?;
+    std::io::copy(&mut reader, &mut target)
+}
+
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+fn restore_snapshot(
+    operations: State<'_, OperationRegistry>,
+    workspace_path: String,
+    snapshot_id: String,
+    op_id: Option<String>,
+    exclude: Option<Vec<String>>,
+    conflict_strategy: Option<String>,
+    skip_system: Option<bool>,
+    subpath: Option<String>,
+    verify: Option<bool>,
+    no_delete: Option<bool>,
+    move_aside_blockers: Option<bool>,
+) -> Result<RestoreResult, String> {
+    let options = RestoreOptions {
+        exclude: exclude.unwrap_or_default(),
+        conflict_strategy: ConflictStrategy::parse(conflict_strategy.as_deref())?,
+        skip_system: skip_system.unwrap_or(true),
+        subpath,
+        verify: verify.unwrap_or(false),
+        no_delete,
+        move_aside_blockers: move_aside_blockers.unwrap_or(false),
+    };
+    let cancel = operations.flag(op_id.as_deref());
+    let result = run_restore(&workspace_path, &snapshot_id, &options, &cancel);
+    if let Some(op_id) = &op_id {
+        operations.finish(op_id);
+    }
+    result
+}
+
+#[tauri::command(async)]
+fn restore_latest(workspace_path: String) -> Result<RestoreResult, String> {
+    let index = load_workspace_index(&workspace_path);
+    let latest = index
+        .snapshots
+        .iter()
+        .max_by_key(|s| s.timestamp)
+        .ok_or("No snapshots to restore in this workspace")?;
+    
+    run_restore(&workspace_path, &latest.id, &RestoreOptions::default(), &AtomicBool::new(false))
+}
+
+#[tauri::command(async)]
+fn restore_at(workspace_path: String, ts: i64) -> Result<RestoreResult, String> {
+    let index = load_workspace_index(&workspace_path);
+    let Some(target) = index
+        .snapshots
+        .iter()
+        .filter(|s| s.timestamp <= ts)
+        .max_by_key(|s| s.timestamp)
+    else {
+        return Err(match index.snapshots.iter().map(|s| s.timestamp).min() {
+            Some(earliest) => format!("No snapshot exists at or before {}. The earliest snapshot is at {}", ts, earliest),
+            None => "No snapshots to restore in this workspace".to_string(),
+        });
+    };
+    
+    run_restore(&workspace_path, &target.id, &RestoreOptions::default(), &AtomicBool::new(false))
+}
+
+fn run_restore(
+    workspace_path: &str,
+    snapshot_id: &str,
+    options: &RestoreOptions,
+    cancel: &AtomicBool,
+) -> Result<RestoreResult, String> {
+    let index = load_workspace_index(workspace_path);
+    let snapshot = index
+        .snapshots
+        .iter()
+        .find(|s| s.id == snapshot_id)
+        .ok_or("Snapshot not found")?;
+    
+    let result = restore_files(workspace_path, snapshot_id, &snapshot.files, options, cancel);
+    log_restore(workspace_path, snapshot_id, snapshot_id, &result);
+    result
+}
+
+/// Logs a restore to operations.log and, unless it was cancelled, records it as the last restore.
+fn log_restore(workspace_path: &str, detail: &str, snapshot_id: &str, result: &Result<RestoreResult, String>) {
+    match result {
+        Ok(r) => {
+            log_operation(
+                workspace_path,
+                "restore",
+                !r.cancelled && r.failed == 0 && r.verification_failures.is_empty(),
+                &[
+                    ("restored", r.restored as u64),
+                    ("deleted", r.deleted as u64),
+                    ("failed", r.failed as u64),
+                    ("skipped", r.skipped as u64),
+                    ("verification_failed", r.verification_failures.len() as u64),
+                    ("deletes_suppressed", r.deletes_suppressed as u64),
+                ],
+                Some(detail.to_string()),
+            );
+            if !r.cancelled {
+                record_last_restore(workspace_path, snapshot_id);
+            }
+        }
+        Err(e) => log_operation(workspace_path, "restore", false, &[], Some(format!("{}: {}", detail, e))),
+    }
+}
+
+fn record_last_restore(workspace_path: &str, snapshot_id: &str) {
+    let mut config = load_global_config();
+    if let Some(workspace) = config.workspaces.iter_mut().find(|w| w.path == workspace_path) {
+        workspace.last_restore = Some((snapshot_id.to_string(), chrono::Utc::now().timestamp_millis()));
+        save_global_config(&config).ok();
+    }
+}
+
+#[tauri::command]
+fn get_last_restore(workspace_path: String) -> Option<(String, i64)> {
+    load_global_config()
+        .workspaces
+        .into_iter()
+        .find(|w| w.path == workspace_path)
+        .and_then(|w| w.last_restore)
+}
+
+#[tauri::command(async)]
+fn restore_merged(workspace_path: String, snapshot_ids: Vec<String>) -> Result<RestoreResult, String> {
+    let index = load_workspace_index(&workspace_path);
+    let mut selected = vec![];
+    for snapshot_id in &snapshot_ids {
+        let snapshot = index
+            .snapshots
+            .iter()
+            .find(|s| &s.id == snapshot_id)
+            .ok_or_else(|| format!("Snapshot not found: {}", snapshot_id))?;
+        selected.push(snapshot);
+    }
+    selected.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
+    
+    // Newest listed snapshot wins for each path
+    let mut seen = std::collections::HashSet::new();
+    let files: Vec<SnapshotFile> = selected
+        .iter()
+        .flat_map(|s| s.files.iter())
+        .filter(|f| seen.insert(f.path.clone()))
+        .cloned()
+        .collect();
+    
+    let result = restore_files(
+        &workspace_path,
+        &format!("{} merged snapshots", selected.len()),
+        &files,
+        &RestoreOptions::default(),
+        &AtomicBool::new(false),
+    );
+    // The newest snapshot wins every path it touches, so it stands in as the last restore
+    if let Some(newest) = selected.first() {
+        log_restore(&workspace_path, &format!("merged {}", snapshot_ids.join(", ")), &newest.id, &result);
+    }
+    result
+}
+
+fn restore_files(
+    workspace_path: &str,
+    label: &str,
+    files: &[SnapshotFile],
+    options: &RestoreOptions,
+    cancel: &AtomicBool,
+) -> Result<RestoreResult, String> {
+    ensure_index_version_supported(workspace_path)?;
+    ensure_workspace_writable(workspace_path)?;
+    
+    let snapshots_dir = get_workspace_snapshots_dir(workspace_path);
+    let restore_lock = get_restore_lock_path(workspace_path);
+    
+    let mut restored = 0u32;
+    let mut failed = 0u32;
+    let mut deleted = 0u32;
+    let mut skipped = 0u32;
+    let mut cancelled = false;
+    let mut failures = vec![];
+    let mut conflicts = vec![];
+    let mut conflicts_skipped = 0u32;
+    let mut pre_restore_snapshot = None;
+    let mut written: Vec<&SnapshotFile> = vec![];
+    let mut deletes_suppressed = 0u32;
+    let mut moved_aside = vec![];
+    let no_delete = options
+        .no_delete
+        .unwrap_or_else(|| load_global_config().restore_no_delete.unwrap_or(false));
+    
+    let is_conflict = |file: &SnapshotFile| is_restore_conflict(workspace_path, &snapshots_dir, file);
+    
+    if options.conflict_strategy == ConflictStrategy::BackupThenOverwrite {
+        let conflicting: Vec<String> = files
+            .iter()
+            .filter(|f| !options.is_excluded(&f.path) && is_conflict(f))
+            .map(|f| f.path.clone())
+            .collect();
+        if !conflicting.is_empty() {
+            pre_restore_snapshot = Some(snapshot_current_files(
+                workspace_path,
+                &conflicting,
+                format!("Before restoring {}", label),
+            )?);
+        }
+    }
+    
+    // Create restore lock to prevent watcher from recording changes. Taken only after the
+    // pre-restore snapshot, whose early return would otherwise skip the lock removal below.
+    fs::write(&restore_lock, format!("{}", chrono::Utc::now().timestamp_millis())).ok();
+    
+    for file in files {
+        if cancel.load(Ordering::Relaxed) {
+            cancelled = true;
+            break;
+        }
+        // Excluded files are left alone and reported with the other skipped files
+        if options.is_excluded(&file.path) {
+            skipped += 1;
+            continue;
+        }
+        let event = match file.event() {
+            Ok(event) => event,
+            Err(reason) => {
+                failed += 1;
+                failures.push((file.path.clone(), reason));
+                continue;
+            }
+        };
+        
+        let backup_full_path = snapshots_dir.join(&file.backup_path);
+        let target_path = PathBuf::from(workspace_path).join(&file.path);
+        
+        if is_conflict(file) {
+            conflicts.push(file.path.clone());
+            if options.conflict_strategy == ConflictStrategy::Skip {
+                conflicts_skipped += 1;
+                continue;
+            }
+        }
+        
+        // The workspace may have changed shape since the snapshot, leaving a file where a directory belongs
+        if event != EventType::Create {
+            if let Some(blocker) = blocking_file_in_path(&target_path) {
+                let relative = blocker
+                    .strip_prefix(workspace_path)
+                    .unwrap_or(&blocker)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                if !options.move_aside_blockers {
+                    failed += 1;
+                    failures.push((
+                        file.path.clone(),
+                        format!("path component is a file, expected directory: {}", relative),
+                    ));
+                    continue;
+                }
+                let moved_to = format!("{}.agentshield-moved-{}", relative, chrono::Utc::now().timestamp_millis());
+                if let Err(e) = with_io_retry(|| fs::rename(&blocker, PathBuf::from(workspace_path).join(&moved_to))) {
+                    failed += 1;
+                    failures.push((file.path.clone(), format!("could not move aside {}: {}", relative, e)));
+                    continue;
+                }
+                moved_aside.push((relative, moved_to));
+            }
+        }
+        
+        match event {
+            EventType::Delete => {
+                match restore_from_backup(&backup_full_path, &target_path, file.codec.as_deref()) {
+                    CopyOutcome::Restored => {
+                        restored += 1;
+                        written.push(file);
+                    }
+                    CopyOutcome::Skipped => skipped += 1,
+                    CopyOutcome::Failed(reason) => {
+                        failed += 1;
+                        failures.push((file.path.clone(), reason));
+                    }
+                }
+            }
+            EventType::Rename => {
+                let copied = match restore_from_backup(&backup_full_path, &target_path, file.codec.as_deref()) {
+                    CopyOutcome::Restored => {
+                        restored += 1;
+                        written.push(file);
+                        true
+                    }
+                    CopyOutcome::Skipped => {
+                        skipped += 1;
+                        true
+                    }
+                    CopyOutcome::Failed(reason) => {
+                        failed += 1;
+                        failures.push((file.path.clone(), reason));
+                        false
+                    }
+                };
+                
+                // Only drop the renamed copy once the original is back, so one version always survives
+                if copied {
+                    if let Some(renamed_to) = &file.renamed_to {
+                        let renamed_path = PathBuf::from(workspace_path).join(renamed_to);
+                        if renamed_path.exists() {
+                            if no_delete {
+                                deletes_suppressed += 1;
+                            } else if with_io_retry(|| fs::remove_file(&renamed_path)).is_ok() {
+                                deleted += 1;
+                            }
+                        }
+                    }
+                }
+            }
+            EventType::Create => {
+                if target_path.exists() {
+                    if no_delete {
+                        deletes_suppressed += 1;
+                    } else if with_io_retry(|| fs::remove_file(&target_path)).is_ok() {
+                        deleted += 1;
+                    }
+                }
+            }
+            EventType::Change => {
+                match restore_from_backup(&backup_full_path, &target_path, file.codec.as_deref()) {
+                    CopyOutcome::Restored => {
+                        restored += 1;
+                        written.push(file);
+                    }
+                    CopyOutcome::Skipped => skipped += 1,
+                    CopyOutcome::Failed(reason) => {
+                        failed += 1;
+                        failures.push((file.path.clone(), reason));
+                    }
+                }
+            }
+        }
+    }
+    
+    // A successful write can still leave the wrong bytes behind (e.g. antivirus rewriting the file)
+    let mut verification_failures = vec![];
+    if options.verify {
+        for file in written {
+            let backup_full_path = snapshots_dir.join(&file.backup_path);
+            let target_path = PathBuf::from(workspace_path).join(&file.path);
+            if !target_matches_backup(&backup_full_path, &target_path, file.codec.as_deref()) {
+                verification_failures.push(file.path.clone());
+            }
+        }
+    }
+    
+    // Schedule lock removal after watcher debounce window
+    let lock_path = restore_lock.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(3500));
+        fs::remove_file(&lock_path).ok();
+    });
+    
+    Ok(RestoreResult {
+        restored,
+        failed,
+        deleted,
+        skipped,
+        cancelled,
+        failures,
+        conflicts,
+        conflicts_skipped,
+        pre_restore_snapshot,
+        verification_failures,
+        deletes_suppressed,
+        moved_aside,
+    })
+}
+
+/// The nearest existing ancestor of `target_path`, if it is a file rather than a directory.
+fn blocking_file_in_path(target_path: &Path) -> Option<PathBuf> {
+    let mut current = target_path.parent();
+    while let Some(dir) = current {
+        if let Ok(meta) = fs::metadata(dir) {
+            return (!meta.is_dir()).then(|| dir.to_path_buf());
+        }
+        current = dir.parent();
+    }
+    None
+}
+
+// Probe with a throwaway file so a read-only mount fails fast instead of as one failure per file
+fn ensure_workspace_writable(workspace_path: &str) -> Result<(), String> {
+    let probe = PathBuf::from(workspace_path).join(format!(".agentshield-write-test-{}", std::process::id()));
+    match fs::File::create(&probe) {
+        Ok(_) => {
+            fs::remove_file(&probe).ok();
+            Ok(())
+        }
+        Err(e) if matches!(e.kind(), std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem) => {
+            Err(format!("Workspace is read-only: {}", workspace_path))
+        }
+        Err(e) => Err(format!("Workspace is not writable: {}", e)),
+    }
+}
+
+// A deleted or renamed-away path that exists again with different content was recreated after the snapshot
+fn is_restore_conflict(workspace_path: &str, snapshots_dir: &Path, file: &SnapshotFile) -> bool {
+    let target_path = PathBuf::from(workspace_path).join(&file.path);
+    matches!(file.event(), Ok(EventType::Delete | EventType::Rename))
+        && target_path.exists()
+        && !target_matches_backup(&snapshots_dir.join(&file.backup_path), &target_path, file.codec.as_deref())
+}
+
+#[tauri::command]
+fn preview_restore(
+    workspace_path: String,
+    snapshot_id: String,
+    exclude: Option<Vec<String>>,
+) -> Result<RestorePlan, String> {
+    let index = load_workspace_index(&workspace_path);
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    let options = RestoreOptions {
+        exclude: exclude.unwrap_or_default(),
+        ..Default::default()
+    };
+    
+    let snapshot = index
+        .snapshots
+        .iter()
+        .find(|s| s.id == snapshot_id)
+        .ok_or("Snapshot not found")?;
+    
+    let workspace_root = PathBuf::from(&workspace_path);
+    let mut plan = RestorePlan::default();
+    let mut dirs_to_create = std::collections::BTreeSet::new();
+    
+    for file in &snapshot.files {
+        if options.is_excluded(&file.path) {
+            plan.excluded.push(file.path.clone());
+            continue;
+        }
+        
+        let Ok(event) = file.event() else {
+            plan.unrecognized.push(file.path.clone());
+            continue;
+        };
+        
+        let target_path = workspace_root.join(&file.path);
+        match event {
+            EventType::Delete | EventType::Change | EventType::Rename => {
+                let backup_full_path = snapshots_dir.join(&file.backup_path);
+                if target_matches_backup(&backup_full_path, &target_path, file.codec.as_deref()) {
+                    plan.unchanged.push(file.path.clone());
+                } else {
+                    plan.to_restore.push(file.path.clone());
+                    // Every missing ancestor up to the workspace root would be created by the copy
+                    let mut parent = target_path.parent();
+                    while let Some(dir) = parent {
+                        if dir == workspace_root || dir.exists() {
+                            break;
+                        }
+                        if let Ok(relative) = dir.strip_prefix(&workspace_root) {
+                            dirs_to_create.insert(relative.to_string_lossy().replace('\\', "/"));
+                        }
+                        parent = dir.parent();
+                    }
+                }
+                if is_restore_conflict(&workspace_path, &snapshots_dir, file) {
+                    plan.conflicts.push(file.path.clone());
+                }
+                if event == EventType::Rename {
+                    if let Some(renamed_to) = &file.renamed_to {
+                        if workspace_root.join(renamed_to).exists() {
+                            plan.to_delete.push(renamed_to.clone());
+                        }
+                    }
+                }
+            }
+            EventType::Create => {
+                if target_path.exists() {
+                    plan.to_delete.push(file.path.clone());
+                }
+            }
+        }
+    }
+    
+    plan.dirs_to_create = dirs_to_create.into_iter().collect();
+    Ok(plan)
+}
+
+#[tauri::command]
+fn estimate_restore(workspace_path: String, snapshot_id: String) -> Result<RestoreEstimate, String> {
+    let index = load_workspace_index(&workspace_path);
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    let options = RestoreOptions::default();
+    let snapshot = index
+        .snapshots
+        .iter()
+        .find(|s| s.id == snapshot_id)
+        .ok_or("Snapshot not found")?;
+    
+    // `size` is the original file size, so compressed blobs are counted at their restored size
+    let mut estimate = RestoreEstimate::default();
+    for file in &snapshot.files {
+        if options.is_excluded(&file.path)
+            || !matches!(file.event(), Ok(EventType::Delete | EventType::Change | EventType::Rename))
+            || file.backup_path.is_empty()
+            || !snapshots_dir.join(&file.backup_path).is_file()
+        {
+            continue;
+        }
+        estimate.files += 1;
+        estimate.total_bytes += file.size;
+    }
+    Ok(estimate)
+}
+
+fn snapshot_current_files(workspace_path: &str, paths: &[String], message: String) -> Result<String, String> {
+    let snapshots_dir = get_workspace_snapshots_dir(workspace_path);
+    fs::create_dir_all(&snapshots_dir).map_err(|e| e.to_string())?;
+    
+    update_workspace_index(workspace_path, |index| {
+        let mut timestamp = chrono::Utc::now().timestamp_millis();
+        while index.snapshots.iter().any(|s| s.timestamp == timestamp) {
+            timestamp += 1;
+        }
+        let snapshot_id = format!("snap_{}", timestamp);
+        
+        let mut files = vec![];
+        for path in paths {
+            let backup_path = backup_name_for(timestamp, path);
+            let backup_full_path = snapshots_dir.join(&backup_path);
+            let size = fs::copy(PathBuf::from(workspace_path).join(path), &backup_full_path)
+                .map_err(|e| format!("Failed to back up {}: {}", path, e))?;
+            files.push(SnapshotFile {
+                path: path.clone(),
+                backup_path,
+                size,
+                event_type: "change".to_string(),
+                renamed_to: None,
+                hash: hash_file(&backup_full_path),
+                codec: None,
+            });
+        }
+        
+        index.snapshots.push(Snapshot {
+            id: snapshot_id.clone(),
+            timestamp,
+            files,
+            message: Some(message),
+            pinned: false,
+            trigger: Some("pre-restore".to_string()),
+        });
+        Ok(snapshot_id)
+    })
+}
+
+fn backup_identical(backup_full_path: &Path, codec: Option<&str>, target_path: &Path) -> bool {
+    use std::io::Read;
+    
+    let (Ok(mut reader_a), Ok(file_b)) = (open_backup(backup_full_path, codec), fs::File::open(target_path)) else {
+        return false;
+    };
+    let mut reader_b = std::io::BufReader::new(file_b);
+    let mut buf_a = [0u8; 8192];
+    let mut buf_b = [0u8; 8192];
+    
+    loop {
+        let read_a = match reader_a.read(&mut buf_a) {
+            Ok(n) => n,
+            Err(_) => return false,
+        };
+        if read_a == 0 {
+            return matches!(reader_b.read(&mut buf_b), Ok(0));
+        }
+        if reader_b.read_exact(&mut buf_b[..read_a]).is_err() {
+            return false;
+        }
+        if buf_a[..read_a] != buf_b[..read_a] {
+            return false;
+        }
+    }
+}
+
+#[tauri::command]
+fn snapshot_as_patch(workspace_path: String, snapshot_id: String) -> Result<String, String> {
+    let index = load_workspace_index(&workspace_path);
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    let options = RestoreOptions::default();
+    let snapshot = index
+        .snapshots
+        .iter()
+        .find(|s| s.id == snapshot_id)
+        .ok_or("Snapshot not found")?;
+    
+    let workspace_root = PathBuf::from(&workspace_path);
+    let mut diffs = String::new();
+    let mut not_diffed = vec![];
+    
+    for file in &snapshot.files {
+        if options.is_excluded(&file.path) {
+            continue;
+        }
+        let Ok(event) = file.event() else {
+            continue;
+        };
+        
+        // The patch takes the workspace to what a restore would leave behind: the backed-up
+        // content, or no file at all for paths the snapshot recorded as created
+        let restored = match event {
+            EventType::Create => None,
+            _ => match read_backup(&snapshots_dir.join(&file.backup_path), file.codec.as_deref()) {
+                Ok(bytes) => Some(bytes),
+                Err(_) => {
+                    not_diffed.push(format!("{} (backup missing)", file.path));
+                    continue;
+                }
+            },
+        };
+        let current = fs::read(workspace_root.join(&file.path)).ok();
+        push_file_patch(&mut diffs, &mut not_diffed, &file.path, current.as_deref(), restored.as_deref());
+        
+        if event == EventType::Rename {
+            if let Some(renamed_to) = &file.renamed_to {
+                if let Ok(current) = fs::read(workspace_root.join(renamed_to)) {
+                    push_file_patch(&mut diffs, &mut not_diffed, renamed_to, Some(&current), None);
+                }
+            }
+        }
+    }
+    
+    // `git apply` ignores anything before the first diff header, so the skipped list can lead the patch
+    let mut patch = String::new();
+    if !not_diffed.is_empty() {
+        patch.push_str("Files not included in this patch:\n");
+        for path in &not_diffed {
+            patch.push_str(&format!("  {}\n", path));
+        }
+        patch.push('\n');
+    }
+    patch.push_str(&diffs);
+    Ok(patch)
+}
+
+// A missing file diffs as empty text; NUL bytes or invalid UTF-8 mark a binary file
+fn patch_text(bytes: Option<&[u8]>) -> Option<&str> {
+    match bytes {
+        None => Some(""),
+        Some(bytes) if bytes.contains(&0) => None,
+        Some(bytes) => std::str::from_utf8(bytes).ok(),
+    }
+}
+
+fn push_file_patch(out: &mut String, not_diffed: &mut Vec<String>, path: &str, current: Option<&[u8]>, restored: Option<&[u8]>) {
+    if current == restored {
+        return;
+    }
+    let (Some(old), Some(new)) = (patch_text(current), patch_text(restored)) else {
+        not_diffed.push(format!("{} (binary)", path));
+        return;
+    };
+    
+    out.push_str(&format!("diff --git a/{0} b/{0}\n", path));
+    let (old_name, new_name) = match (current, restored) {
+        (None, _) => {
+            out.push_str("new file mode 100644\n");
+            ("/dev/null".to_string(), format!("b/{}", path))
+        }
+        (_, None) => {
+            out.push_str("deleted file mode 100644\n");
+            (format!("a/{}", path), "/dev/null".to_string())
+        }
+        _ => (format!("a/{}", path), format!("b/{}", path)),
+    };
+    let diff = similar::TextDiff::from_lines(old, new);
+    out.push_str(&diff.unified_diff().header(&old_name, &new_name).to_string());
+}
+
+#[tauri::command]
+fn diff_snapshot_to_current(workspace_path: String, snapshot_id: String) -> Result<Vec<FileDiff>, String> {
+    let index = load_workspace_index(&workspace_path);
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    
+    let snapshot = index
+        .snapshots
+        .iter()
+        .find(|s| s.id == snapshot_id)
+        .ok_or("Snapshot not found")?;
+    
+    let mut diffs = vec![];
+    
+    for file in &snapshot.files {
+        let target_path = PathBuf::from(&workspace_path).join(&file.path);
+        let current_size = fs::metadata(&target_path).ok().filter(|m| m.is_file()).map(|m| m.len());
+        
+        if file.event() == Ok(EventType::Create) {
+            // The file did not exist at snapshot time, so anything on disk is new
+            diffs.push(FileDiff {
+                path: file.path.clone(),
+                status: if current_size.is_some() {
+                    DiffStatus::OnlyOnDisk
+                } else {
+                    DiffStatus::Unchanged
+                },
+                snapshot_size: None,
+                current_size,
+            });
+            continue;
+        }
+        
+        if file.event() == Ok(EventType::Rename) {
+            if let Some(renamed_to) = &file.renamed_to {
+                let renamed_path = PathBuf::from(&workspace_path).join(renamed_to);
+                if let Ok(meta) = fs::metadata(&renamed_path) {
+                    diffs.push(FileDiff {
+                        path: renamed_to.clone(),
+                        status: DiffStatus::OnlyOnDisk,
+                        snapshot_size: None,
+                        current_size: Some(meta.len()),
+                    });
+                }
+            }
+        }
+        
+        let status = match current_size {
+            None => DiffStatus::MissingOnDisk,
+            Some(size) if size != file.size => DiffStatus::Modified,
+            Some(_) => {
+                let backup_full_path = snapshots_dir.join(&file.backup_path);
+                let unchanged = match (&file.hash, &file.codec) {
+                    // A raw blob's recorded hash is its content hash, so only the target needs reading
+                    (Some(hash), None) => hash_file(&target_path).as_ref() == Some(hash),
+                    // A missing blob cannot vouch for the file, so it never counts as unchanged
+                    _ => backup_identical(&backup_full_path, file.codec.as_deref(), &target_path),
+                };
+                if unchanged {
+                    DiffStatus::Unchanged
+                } else {
+                    DiffStatus::Modified
+                }
+            }
+        };
+        
+        diffs.push(FileDiff {
+            path: file.path.clone(),
+            status,
+            snapshot_size: Some(file.size),
+            current_size,
+        });
+    }
+    
+    Ok(diffs)
+}
+
+/// Removes now-empty directories between `path` and `root`, leaving `root` itself in place.
+fn remove_empty_parent_dirs(path: &Path, root: &Path) {
+    let mut current = path.parent();
+    while let Some(dir) = current {
+        if dir == root || !dir.starts_with(root) {
+            break;
+        }
+        if fs::remove_dir(dir).is_err() {
+            break;
+        }
+        current = dir.parent();
+    }
+}
+
+// Decoded copies from `get_backup_file_path`; callers keep them open, so they are cleared on the next startup
+fn get_extract_dir() -> PathBuf {
+    std::env::temp_dir().join("agentshield")
+}
+
+#[tauri::command]
+fn get_backup_file_path(workspace_path: String, snapshot_id: String, file_path: String) -> Result<String, String> {
+    let index = load_workspace_index(&workspace_path);
+    let snapshot = index
+        .snapshots
+        .iter()
+        .find(|s| s.id == snapshot_id)
+        .ok_or("Snapshot not found")?;
+    let file = snapshot
+        .files
+        .iter()
+        .find(|f| f.path == file_path)
+        .ok_or("File not found in snapshot")?;
+    
+    let backup_full_path = get_workspace_snapshots_dir(&workspace_path).join(&file.backup_path);
+    if file.backup_path.is_empty() || !backup_full_path.is_file() {
+        return Err("Backup file does not exist".to_string());
+    }
+    if file.codec.is_none() {
+        return Ok(backup_full_path.to_string_lossy().to_string());
+    }
+    
+    // Callers open the returned path directly, so hand them a decoded copy of compressed backups,
+    // mirrored under a per-snapshot dir so distinct paths can never share a copy
+    let is_plain = |path: &Path| path.components().all(|c| matches!(c, std::path::Component::Normal(_)));
+    if !is_plain(Path::new(&snapshot.id)) || !is_plain(Path::new(&file.path)) {
+        return Err("Backup path is not a plain relative path".to_string());
+    }
+    let extracted = get_extract_dir().join(&snapshot.id).join(&file.path);
+    if let Some(parent) = extracted.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    copy_backup_to(&backup_full_path, file.codec.as_deref(), &extracted).map_err(|e| e.to_string())?;
+    
+    Ok(extracted.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn extract_snapshot_file(
+    workspace_path: String,
+    snapshot_id: String,
+    file_path: String,
+    output_path: String,
+) -> Result<(), String> {
+    let index = load_workspace_index(&workspace_path);
+    let snapshot = index
+        .snapshots
+        .iter()
+        .find(|s| s.id == snapshot_id)
+        .ok_or("Snapshot not found")?;
+    let file = snapshot
+        .files
+        .iter()
+        .find(|f| f.path == file_path)
+        .ok_or("File not found in snapshot")?;
+    
+    let backup_full_path = get_workspace_snapshots_dir(&workspace_path).join(&file.backup_path);
+    if file.backup_path.is_empty() || !backup_full_path.is_file() {
+        return Err("Backup file does not exist".to_string());
+    }
+    
+    copy_backup_to(&backup_full_path, file.codec.as_deref(), Path::new(&output_path)).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command(async)]
+fn get_snapshot_image_preview(
+    workspace_path: String,
+    snapshot_id: String,
+    file_path: String,
+    max_dim: u32,
+) -> Result<Vec<u8>, String> {
+    if max_dim == 0 {
+        return Err("max_dim must be greater than zero".to_string());
+    }
+    let index = load_workspace_index(&workspace_path);
+    let snapshot = index
+        .snapshots
+        .iter()
+        .find(|s| s.id == snapshot_id)
+        .ok_or("Snapshot not found")?;
+    let file = snapshot
+        .files
+        .iter()
+        .find(|f| f.path == file_path)
+        .ok_or("File not found in snapshot")?;
+    
+    let backup_full_path = get_workspace_snapshots_dir(&workspace_path).join(&file.backup_path);
+    if file.backup_path.is_empty() || !backup_full_path.is_file() {
+        return Err("Backup file does not exist".to_string());
+    }
+    
+    let bytes = read_backup(&backup_full_path, file.codec.as_deref()).map_err(|e| e.to_string())?;
+    
+    // Sniff the content rather than trusting the extension
+    let reader = image::ImageReader::new(std::io::Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?;
+    if reader.format().is_none() {
+        return Err(format!("Not an image file: {}", file_path));
+    }
+    let image = reader.decode().map_err(|e| format!("Failed to decode image: {}", e))?;
+    let preview = if image.width() > max_dim || image.height() > max_dim {
+        image.thumbnail(max_dim, max_dim)
+    } else {
+        image
+    };
+    
+    let mut png = std::io::Cursor::new(vec![]);
+    preview.write_to(&mut png, image::ImageFormat::Png).map_err(|e| e.to_string())?;
+    Ok(png.into_inner())
+}
+
+/// Rewrites absolute `path`/`renamedTo` entries left by older indexes as workspace-relative paths.
+/// Pass `previous_root` when the workspace has been relocated since those entries were written.
+#[tauri::command]
+fn normalize_index_paths(workspace_path: String, previous_root: Option<String>) -> Result<usize, String> {
+    let mut roots = vec![PathBuf::from(&workspace_path), canonicalize_path(&workspace_path)];
+    if let Some(previous_root) = &previous_root {
+        roots.push(PathBuf::from(previous_root));
+    }
+    let relativize = |value: &str| -> Option<String> {
+        let path = Path::new(value);
+        if !path.is_absolute() {
+            return None;
+        }
+        roots
+            .iter()
+            .find_map(|root| path.strip_prefix(root).ok())
+            .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+    };
+    
+    update_workspace_index(&workspace_path, |index| {
+        let mut fixed = 0;
+        for file in index.snapshots.iter_mut().flat_map(|s| s.files.iter_mut()) {
+            if let Some(relative) = relativize(&file.path) {
+                file.path = relative;
+                fixed += 1;
+            }
+            if let Some(relative) = file.renamed_to.as_deref().and_then(relativize) {
+                file.renamed_to = Some(relative);
+                fixed += 1;
+            }
+        }
+        Ok(fixed)
+    })
+}
+
+#[tauri::command(async)]
+fn prune_dangling_entries(workspace_path: String) -> Result<usize, String> {
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    
+    update_workspace_index(&workspace_path, |index| {
+        let mut removed = 0usize;
+        index.snapshots.retain_mut(|snapshot| {
+            let before = snapshot.files.len();
+            // "create" entries never had a backup written, so a missing blob is expected for them
+            snapshot.files.retain(|f| {
+                f.backup_path.is_empty()
+                    || f.event() == Ok(EventType::Create)
+                    || snapshots_dir.join(&f.backup_path).is_file()
+            });
+            removed += before - snapshot.files.len();
+            before == snapshot.files.len() || !snapshot.files.is_empty()
+        });
+        Ok(removed)
+    })
+}
+
+#[tauri::command(async)]
+fn recompress_workspace(workspace_path: String) -> Result<(usize, u64), String> {
+    ensure_index_version_supported(&workspace_path)?;
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    
+    let raw_backups: std::collections::HashSet<String> = load_workspace_index(&workspace_path)
+        .snapshots
+        .iter()
+        .flat_map(|s| s.files.iter())
+        .filter(|f| f.codec.is_none() && !f.backup_path.is_empty() && f.event() != Ok(EventType::Create))
+        .map(|f| f.backup_path.clone())
+        .collect();
+    
+    // Compression can take minutes on a large store, and the daemon stops waiting for the index lock
+    // after 5s; write the compressed blobs first and hold the lock only to swap the entries over
+    let mut candidates = vec![];
+    for backup_path in raw_backups {
+        let source = snapshots_dir.join(&backup_path);
+        let Ok(original_size) = fs::metadata(&source).map(|m| m.len()) else {
+            continue;
+        };
+        let compressed_path = format!("{}.gz", backup_path);
+        let dest = snapshots_dir.join(&compressed_path);
+        let Ok(compressed_size) = gzip_file(&source, &dest) else {
+            fs::remove_file(&dest).ok();
+            continue;
+        };
+        // Incompressible content (images, archives) stays raw
+        if compressed_size >= original_size {
+            fs::remove_file(&dest).ok();
+            continue;
+        }
+        let hash = hash_file(&dest);
+        candidates.push((backup_path, compressed_path, hash, original_size - compressed_size));
+    }
+    
+    let swapped = update_workspace_index(&workspace_path, |index| {
+        let mut swapped = vec![];
+        for (backup_path, compressed_path, hash, _) in &candidates {
+            let mut any = false;
+            // Entries removed or rewritten since the blobs were compressed are left alone
+            for file in index.snapshots.iter_mut().flat_map(|s| s.files.iter_mut()) {
+                if file.backup_path == *backup_path && file.codec.is_none() {
+                    file.backup_path = compressed_path.clone();
+                    file.codec = Some("gzip".to_string());
+                    file.hash = hash.clone();
+                    any = true;
+                }
+            }
+            swapped.push(any);
+        }
+        Ok(swapped)
+    });
+    let swapped = match swapped {
+        Ok(swapped) => swapped,
+        Err(e) => {
+            for (_, compressed_path, _, _) in &candidates {
+                fs::remove_file(snapshots_dir.join(compressed_path)).ok();
+            }
+            return Err(e);
+        }
+    };
+    
+    // Raw copies go only after the index pointing at the compressed ones is saved
+    let mut compressed = 0usize;
+    let mut saved_bytes = 0u64;
+    for ((backup_path, compressed_path, _, saved), swapped) in candidates.into_iter().zip(swapped) {
+        if swapped {
+            fs::remove_file(snapshots_dir.join(&backup_path)).ok();
+            compressed += 1;
+            saved_bytes += saved;
+        } else {
+            fs::remove_file(snapshots_dir.join(&compressed_path)).ok();
+        }
+    }
+    
+    Ok((compressed, saved_bytes))
+}
+
+fn gzip_file(source: &Path, dest: &Path) -> std::io::Result<u64> {
+    let mut reader = std::io::BufReader::new(fs::File::open(source)?);
+    let mut encoder = flate2::write::GzEncoder::new(fs::File::create(dest)?, flate2::Compression::default());
+    std::io::copy(&mut reader, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    fs::metadata(dest).map(|m| m.len())
+}
+
+#[tauri::command(async)]
+fn clean_old_snapshots(
+    operations: State<'_, OperationRegistry>,
+    workspace_path: String,
+    max_age_days: i64,
+    op_id: Option<String>,
+) -> Result<(usize, u64), String> {
+    let cancel = operations.flag(op_id.as_deref());
+    let result = run_clean_old_snapshots(&workspace_path, max_age_days, &cancel);
+    if let Some(op_id) = &op_id {
+        operations.finish(op_id);
+    }
+    result
+}
+
+fn run_clean_old_snapshots(
+    workspace_path: &str,
+    max_age_days: i64,
+    cancel: &AtomicBool,
+) -> Result<(usize, u64), String> {
+    let snapshots_dir = get_workspace_snapshots_dir(workspace_path);
+    let cutoff = clean_cutoff(max_age_days);
+    
+    // Only the index edit happens under the lock; blobs are deleted once the trimmed index is saved
+    let result = update_workspace_index(workspace_path, |index| {
+        let mut removed = vec![];
+        let mut to_keep = vec![];
+        
+        for snapshot in std::mem::take(&mut index.snapshots) {
+            if is_clean_candidate(&snapshot, cutoff) && !cancel.load(Ordering::Relaxed) {
+                removed.push(snapshot);
+            } else {
+                to_keep.push(snapshot);
+            }
+        }
+        
+        index.snapshots = to_keep;
+        
+        Ok(removed)
+    })
+    .map(|removed| {
+        let mut freed_bytes = 0u64;
+        for file in removed.iter().flat_map(|s| s.files.iter()) {
+            let backup_path = snapshots_dir.join(&file.backup_path);
+            if let Some(meta) = fs::metadata(&backup_path).ok().filter(|m| m.is_file()) {
+                freed_bytes += meta.len();
+                if fs::remove_file(&backup_path).is_ok() {
+                    remove_empty_parent_dirs(&backup_path, &snapshots_dir);
+                }
+            }
+        }
+        (removed.len(), freed_bytes)
+    });
+    
+    match &result {
+        Ok((removed, freed_bytes)) => log_operation(
+            workspace_path,
+            "clean",
+            true,
+            &[("removed", *removed as u64), ("freed_bytes", *freed_bytes)],
+            Some(format!("older than {} days", max_age_days)),
+        ),
+        Err(e) => log_operation(workspace_path, "clean", false, &[], Some(e.clone())),
+    }
+    result
+}
+
+fn clean_cutoff(max_age_days: i64) -> i64 {
+    chrono::Utc::now().timestamp_millis() - (max_age_days * 24 * 60 * 60 * 1000)
+}
+
+fn is_clean_candidate(snapshot: &Snapshot, cutoff: i64) -> bool {
+    snapshot.timestamp < cutoff && !snapshot.pinned
+}
+
+#[tauri::command]
+fn preview_clean_old_snapshots(workspace_path: String, max_age_days: i64) -> (usize, u64) {
+    let index = load_workspace_index(&workspace_path);
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    let cutoff = clean_cutoff(max_age_days);
+    
+    let mut removed = 0usize;
+    let mut freed_bytes = 0u64;
+    for snapshot in index.snapshots.iter().filter(|s| is_clean_candidate(s, cutoff)) {
+        for file in &snapshot.files {
+            if let Ok(meta) = fs::metadata(snapshots_dir.join(&file.backup_path)) {
+                freed_bytes += meta.len();
+            }
+        }
+        removed += 1;
+    }
+    
+    (removed, freed_bytes)
+}
+
+#[tauri::command(async)]
+fn move_workspace_storage(
+    launched: State<'_, LaunchedPids>,
+    workspace_path: String,
+    new_storage_root: String,
+) -> Result<(), String> {
+    // A daemon that lost its pidfile still writes into the store, so the process scan counts too
+    if detect_shield_status(&launched, &workspace_path).running {
+        return Err("Stop shield before moving its storage".to_string());
+    }
+    
+    let current = get_shield_dir(&workspace_path);
+    if !current.is_dir() {
+        return Err("Workspace has no backup storage to move".to_string());
+    }
+    
+    // Name the store after the workspace, with a path hash so same-named projects can share a root
+    let canonical = canonicalize_path(&workspace_path);
+    let name = canonical
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("workspace")
+        .to_string();
+    let digest = {
+        use sha2::{Digest, Sha256};
+        format!("{:x}", Sha256::digest(canonical.to_string_lossy().as_bytes()))
+    };
+    let root = canonicalize_path(&new_storage_root);
+    let target = root.join(format!("{}-{}", name, &digest[..12]));
+    
+    if target.exists() {
+        return Err(format!("Destination already exists: {}", target.display()));
+    }
+    fs::create_dir_all(&root).map_err(|e| e.to_string())?;
+    
+    // Stage the redirect up front; the old store is only deleted once the marker points at the new one
+    let marker = PathBuf::from(&workspace_path).join(SHIELD_DIR);
+    let staged_marker = PathBuf::from(&workspace_path).join(format!("{}.moving", SHIELD_DIR));
+    fs::write(&staged_marker, target.to_string_lossy().as_bytes()).map_err(|e| e.to_string())?;
+    
+    // rename fails across filesystems, which is the common case here, so fall back to copy + delete
+    let moved = fs::rename(&current, &target).is_ok();
+    if !moved {
+        if let Err(e) = copy_dir_recursive(&current, &target) {
+            fs::remove_dir_all(&target).ok();
+            fs::remove_file(&staged_marker).ok();
+            return Err(format!("Failed to copy backup storage: {}", e));
+        }
+    }
+    
+    // A copied local store still sits where the marker goes, so park it until the marker is in place
+    let parked = (!moved && marker.is_dir()).then(|| {
+        PathBuf::from(&workspace_path).join(format!("{}.old-{}", SHIELD_DIR, chrono::Utc::now().timestamp_millis()))
+    });
+    if let Some(parked) = &parked {
+        if let Err(e) = fs::rename(&current, parked) {
+            fs::remove_dir_all(&target).ok();
+            fs::remove_file(&staged_marker).ok();
+            return Err(format!("Failed to move old backup storage aside: {}", e));
+        }
+    }
+    
+    if let Err(e) = fs::rename(&staged_marker, &marker) {
+        // Put the store back where it was so the workspace keeps its history
+        match &parked {
+            Some(parked) => {
+                fs::rename(parked, &current).ok();
+                fs::remove_dir_all(&target).ok();
+            }
+            None if moved => {
+                fs::rename(&target, &current).ok();
+            }
+            None => {
+                fs::remove_dir_all(&target).ok();
+            }
+        }
+        fs::remove_file(&staged_marker).ok();
+        return Err(format!("Failed to write storage redirect: {}", e));
+    }
+    
+    if !moved {
+        let old_store = parked.as_ref().unwrap_or(&current);
+        fs::remove_dir_all(old_store)
+            .map_err(|e| format!("Storage moved, but the old copy could not be removed from {}: {}", old_store.display(), e))?;
+    }
+    
+    Ok(())
+}
+
+fn copy_dir_recursive(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let dest = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_recursive(&entry.path(), &dest)?;
+        } else {
+            fs::copy(entry.path(), &dest)?;
+        }
+    }
+    Ok(())
+}
+
+fn collect_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = vec![];
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                files.extend(collect_files(&path));
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+#[tauri::command(async)]
+fn verify_workspace(workspace_path: String) -> VerifyReport {
+    let mut report = VerifyReport::default();
+    // Read the repairs before loading, since loading persists them
+    if let Some(mut raw) = read_index_as::<BackupIndex>(&workspace_path) {
+        report.id_repairs = repair_snapshot_ids(&mut raw.snapshots);
+    }
+    let index = load_workspace_index(&workspace_path);
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    
+    // "create" entries record a path only; there is no backup blob to check
+    let files: Vec<&SnapshotFile> = index
+        .snapshots
+        .iter()
+        .flat_map(|s| s.files.iter())
+        .filter(|f| f.event() != Ok(EventType::Create) && !f.backup_path.is_empty())
+        .collect();
+    
+    // Hashing dominates on large stores; split it across a bounded set of threads and
+    // merge chunk results back in order so the report matches a sequential pass
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(VERIFY_MAX_THREADS);
+    let chunk_size = files.len().div_ceil(workers).max(1);
+    let outcomes: Vec<BlobCheck> = std::thread::scope(|scope| {
+        let handles: Vec<_> = files
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let snapshots_dir = &snapshots_dir;
+                scope.spawn(move || chunk.iter().map(|file| check_backup_blob(snapshots_dir, file)).collect::<Vec<_>>())
+            })
+            .collect();
+        handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+    });
+    
+    for (file, outcome) in files.iter().zip(outcomes) {
+        match outcome {
+            BlobCheck::Ok => report.ok += 1,
+            BlobCheck::Missing => {
+                report.missing += 1;
+                report.missing_files.push(file.backup_path.clone());
+            }
+            BlobCheck::Corrupt => {
+                report.corrupt += 1;
+                report.corrupt_files.push(file.backup_path.clone());
+            }
+        }
+    }
+    
+    report
+}
+
+enum BlobCheck {
+    Ok,
+    Missing,
+    Corrupt,
+}
+
+fn check_backup_blob(snapshots_dir: &Path, file: &SnapshotFile) -> BlobCheck {
+    let backup_full_path = snapshots_dir.join(&file.backup_path);
+    if !backup_full_path.is_file() {
+        return BlobCheck::Missing;
+    }
+    match &file.hash {
+        Some(expected) if hash_file(&backup_full_path).as_ref() != Some(expected) => BlobCheck::Corrupt,
+        _ => BlobCheck::Ok,
+    }
+}
+
+/// Size of every distinct backup blob referenced by `snapshots` that still exists on disk.
+/// Sizes of every backup blob referenced by `snapshots`, keyed by backup path; missing blobs are left out.
+fn backup_sizes(snapshots_dir: &Path, snapshots: &[Snapshot]) -> HashMap<String, u64> {
+    let backup_paths: std::collections::HashSet<&str> = snapshots
+        .iter()
+        .flat_map(|s| s.files.iter())
+        .filter(|f| !f.backup_path.is_empty())
+        .map(|f| f.backup_path.as_str())
+        .collect();
+    backup_paths
+        .into_iter()
+        .filter_map(|backup_path| {
+            let meta = fs::metadata(snapshots_dir.join(backup_path)).ok()?;
+            Some((backup_path.to_string(), meta.len()))
+        })
+        .collect()
+}
+
+/// Backups of `removed` that no snapshot in `remaining` still references.
+fn unreferenced_backups<'a>(removed: &'a Snapshot, remaining: &[Snapshot]) -> Vec<&'a str> {
+    removed
+        .files
+        .iter()
+        .filter(|file| !file.backup_path.is_empty())
+        .filter(|file| {
+            !remaining
+                .iter()
+                .any(|s| s.files.iter().any(|f| f.backup_path == file.backup_path))
+        })
+        .map(|file| file.backup_path.as_str())
+        .collect()
+}
+
+/// Splits a snapshot's backup bytes into those only it references and those other snapshots also use.
+fn snapshot_backup_breakdown(workspace_path: &str, snapshot_id: &str) -> Result<(u64, u64), String> {
+    let index = load_workspace_index(workspace_path);
+    let snapshots_dir = get_workspace_snapshots_dir(workspace_path);
+    let snapshot = index
+        .snapshots
+        .iter()
+        .find(|s| s.id == snapshot_id)
+        .ok_or("Snapshot not found")?;
+    
+    let backup_paths: std::collections::HashSet<&str> = snapshot
+        .files
+        .iter()
+        .filter(|f| !f.backup_path.is_empty())
+        .map(|f| f.backup_path.as_str())
+        .collect();
+    
+    let mut unique_bytes = 0u64;
+    let mut shared_bytes = 0u64;
+    for backup_path in backup_paths {
+        let Ok(meta) = fs::metadata(snapshots_dir.join(backup_path)) else {
+            continue;
+        };
+        let shared = index
+            .snapshots
+            .iter()
+            .filter(|s| s.id != snapshot_id)
+            .any(|s| s.files.iter().any(|f| f.backup_path == backup_path));
+        if shared {
+            shared_bytes += meta.len();
+        } else {
+            unique_bytes += meta.len();
+        }
+    }
+    
+    Ok((unique_bytes, shared_bytes))
+}
+
+#[tauri::command]
+fn preview_delete_snapshot(workspace_path: String, snapshot_id: String) -> Result<u64, String> {
+    snapshot_backup_breakdown(&workspace_path, &snapshot_id).map(|(unique_bytes, _)| unique_bytes)
+}
+
+#[tauri::command]
+fn get_snapshot_storage_breakdown(workspace_path: String, snapshot_id: String) -> Result<(u64, u64), String> {
+    snapshot_backup_breakdown(&workspace_path, &snapshot_id)
+}
+
+#[tauri::command]
+fn enforce_storage_budget(workspace_path: String) -> Result<(usize, u64), String> {
+    let budget = load_global_config()
+        .workspaces
+        .into_iter()
+        .find(|w| w.path == workspace_path)
+        .and_then(|w| w.max_storage_bytes)
+        .ok_or("No storage budget is set for this workspace")?;
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    
+    // Stat the store before taking the lock, which the daemon only waits 5s for; blobs written
+    // since then are stat'ed under the lock, and deletes wait until the trimmed index is saved
+    let mut sizes = backup_sizes(&snapshots_dir, &load_workspace_index(&workspace_path).snapshots);
+    let result = update_workspace_index(&workspace_path, |index| {
+        index.snapshots.sort_by_key(|s| s.timestamp);
+        for file in index.snapshots.iter().flat_map(|s| s.files.iter()) {
+            if !file.backup_path.is_empty() && !sizes.contains_key(&file.backup_path) {
+                if let Ok(meta) = fs::metadata(snapshots_dir.join(&file.backup_path)) {
+                    sizes.insert(file.backup_path.clone(), meta.len());
+                }
+            }
+        }
+        let referenced: std::collections::HashSet<&str> = index
+            .snapshots
+            .iter()
+            .flat_map(|s| s.files.iter())
+            .map(|f| f.backup_path.as_str())
+            .collect();
+        let mut store_size: u64 = referenced.iter().filter_map(|p| sizes.get(*p)).sum();
+        let mut removed = 0usize;
+        let mut freed_bytes = 0u64;
+        let mut doomed = vec![];
+        
+        while store_size > budget {
+            let Some(position) = index.snapshots.iter().position(|s| !s.pinned) else {
+                break;
+            };
+            let snapshot = index.snapshots.remove(position);
+            for backup_path in unreferenced_backups(&snapshot, &index.snapshots) {
+                let freed = sizes.get(backup_path).copied().unwrap_or(0);
+                store_size = store_size.saturating_sub(freed);
+                freed_bytes += freed;
+                doomed.push(backup_path.to_string());
+            }
+            removed += 1;
+        }
+        
+        Ok((removed, freed_bytes, doomed))
+    });
+    let (removed, freed_bytes, doomed) = match result {
+        Ok(result) => result,
+        Err(e) => {
+            log_operation(&workspace_path, "budget", false, &[], Some(e.clone()));
+            return Err(e);
+        }
+    };
+    
+    for backup_path in doomed {
+        let backup_path = snapshots_dir.join(backup_path);
+        if fs::remove_file(&backup_path).is_ok() {
+            remove_empty_parent_dirs(&backup_path, &snapshots_dir);
+        }
+    }
+    log_operation(
+        &workspace_path,
+        "budget",
+        true,
+        &[("removed", removed as u64), ("freed_bytes", freed_bytes)],
+        Some(format!("budget {} bytes", budget)),
+    );
+    
+    Ok((removed, freed_bytes))
+}
+
+#[tauri::command]
+fn set_snapshot_pinned(workspace_path: String, snapshot_id: String, pinned: bool) -> Result<(), String> {
+    update_workspace_index(&workspace_path, |index| {
+        let snapshot = index
+            .snapshots
+            .iter_mut()
+            .find(|s| s.id == snapshot_id)
+            .ok_or("Snapshot not found")?;
+        snapshot.pinned = pinned;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+fn merge_snapshots(workspace_path: String, snapshot_ids: Vec<String>) -> Result<String, String> {
+    if snapshot_ids.len() < 2 {
+        return Err("Select at least two snapshots to merge".to_string());
+    }
+    
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    
+    update_workspace_index(&workspace_path, |index| {
+        for id in &snapshot_ids {
+            if !index.snapshots.iter().any(|s| &s.id == id) {
+                return Err(format!("Snapshot not found: {}", id));
+            }
+        }
+        
+        let (mut merged, remaining): (Vec<Snapshot>, Vec<Snapshot>) = std::mem::take(&mut index.snapshots)
+            .into_iter()
+            .partition(|s| snapshot_ids.contains(&s.id));
+        index.snapshots = remaining;
+        merged.sort_by_key(|s| s.timestamp);
+        
+        // Walk newest-first so the first version seen for each path is the one kept
+        let mut kept: Vec<SnapshotFile> = vec![];
+        let mut superseded: Vec<SnapshotFile> = vec![];
+        for snapshot in merged.iter().rev() {
+            for file in &snapshot.files {
+                if kept.iter().any(|k| k.path == file.path) {
+                    superseded.push(file.clone());
+                } else {
+                    kept.push(file.clone());
+                }
+            }
+        }
+        
+        for file in &superseded {
+            let still_referenced = kept.iter().any(|k| k.backup_path == file.backup_path)
+                || index
+                    .snapshots
+                    .iter()
+                    .any(|s| s.files.iter().any(|f| f.backup_path == file.backup_path));
+            if !file.backup_path.is_empty() && !still_referenced {
+                let backup_path = snapshots_dir.join(&file.backup_path);
+                if fs::remove_file(&backup_path).is_ok() {
+                    remove_empty_parent_dirs(&backup_path, &snapshots_dir);
+                }
+            }
+        }
+        
+        let timestamp = merged.last().map(|s| s.timestamp).unwrap_or_default();
+        let snapshot_id = format!("snap_{}", timestamp);
+        // Keep the trigger only when every merged snapshot agrees on it
+        let trigger = merged.first().and_then(|s| s.trigger.clone()).filter(|t| {
+            merged.iter().all(|s| s.trigger.as_deref() == Some(t.as_str()))
+        });
+        kept.reverse();
+        index.snapshots.push(Snapshot {
+            id: snapshot_id.clone(),
+            timestamp,
+            files: kept,
+            message: Some(format!("Merged {} snapshots", merged.len())),
+            pinned: merged.iter().any(|s| s.pinned),
+            trigger,
+        });
+        index.snapshots.sort_by_key(|s| s.timestamp);
+        
+        Ok(snapshot_id)
+    })
+}
+
+fn list_orphaned_backups(workspace_path: &str) -> Vec<String> {
+    let index = load_workspace_index(workspace_path);
+    let snapshots_dir = get_workspace_snapshots_dir(workspace_path);
+    let referenced: std::collections::HashSet<String> = index
+        .snapshots
+        .iter()
+        .flat_map(|s| s.files.iter())
+        .map(|f| f.backup_path.replace('\\', "/"))
+        .collect();
+    
+    let mut orphaned: Vec<String> = collect_files(&snapshots_dir)
+        .into_iter()
+        .filter_map(|path| {
+            let relative = path.strip_prefix(&snapshots_dir).ok()?;
+            Some(relative.to_string_lossy().replace('\\', "/"))
+        })
+        .filter(|relative| !referenced.contains(relative))
+        .collect();
+    orphaned.sort();
+    orphaned
+}
+
+#[tauri::command]
+fn snapshots_using_backup(workspace_path: String, backup_path: String) -> Vec<String> {
+    load_workspace_index(&workspace_path)
+        .snapshots
+        .into_iter()
+        .filter(|s| s.files.iter().any(|f| f.backup_path == backup_path))
+        .map(|s| s.id)
+        .collect()
+}
+
+#[tauri::command]
+fn find_orphaned_backups(workspace_path: String) -> Vec<String> {
+    list_orphaned_backups(&workspace_path)
+}
+
+#[tauri::command]
+fn remove_orphaned_backups(workspace_path: String) -> Result<u64, String> {
+    ensure_index_version_supported(&workspace_path)?;
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    let mut freed_bytes = 0u64;
+    
+    for relative in list_orphaned_backups(&workspace_path) {
+        let backup_path = snapshots_dir.join(&relative);
+        let Ok(meta) = fs::metadata(&backup_path) else {
+            continue;
+        };
+        // The daemon writes backups before indexing them, so very recent files may not be orphans yet
+        let recently_written = meta
+            .modified()
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map(|age| age < std::time::Duration::from_secs(60))
+            .unwrap_or(true);
+        if recently_written {
+            continue;
+        }
+        
+        let size = meta.len();
+        if fs::remove_file(&backup_path).is_ok() {
+            freed_bytes += size;
+            remove_empty_parent_dirs(&backup_path, &snapshots_dir);
+        }
+    }
+    
+    Ok(freed_bytes)
+}
+
+#[tauri::command]
+fn purge_workspace_history(workspace_path: String) -> Result<(usize, u64), String> {
+    if check_shield_running(&workspace_path).running {
+        return Err("Stop shield before purging this workspace's history".to_string());
+    }
+    
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    
+    let result = update_workspace_index(&workspace_path, |index| {
+        let removed = index.snapshots.len();
+        let mut freed_bytes = 0u64;
+        
+        for backup_path in collect_files(&snapshots_dir) {
+            let size = fs::metadata(&backup_path).map(|m| m.len()).unwrap_or(0);
+            if fs::remove_file(&backup_path).is_ok() {
+                freed_bytes += size;
+                remove_empty_parent_dirs(&backup_path, &snapshots_dir);
+            }
+        }
+        
+        index.snapshots.clear();
+        
+        Ok((removed, freed_bytes))
+    });
+    
+    match &result {
+        Ok((removed, freed_bytes)) => log_operation(
+            &workspace_path,
+            "purge",
+            true,
+            &[("removed", *removed as u64), ("freed_bytes", *freed_bytes)],
+            None,
+        ),
+        Err(e) => log_operation(&workspace_path, "purge", false, &[], Some(e.clone())),
+    }
+    result
+}
+
+#[tauri::command]
+fn preview_purge_workspace_history(workspace_path: String) -> (usize, u64) {
+    // Purging deletes every blob in the snapshots directory, orphans included, so size it the same way
+    let freed_bytes = collect_files(&get_workspace_snapshots_dir(&workspace_path))
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|m| m.len())
+        .sum();
+    (count_workspace_snapshots(&workspace_path), freed_bytes)
+}
+
+#[tauri::command]
+fn export_workspace_index(workspace_path: String, output_path: String) -> Result<(), String> {
+    let workspace = load_global_config()
+        .workspaces
+        .into_iter()
+        .find(|w| w.path == workspace_path);
+    let export = WorkspaceExport {
+        index: load_workspace_index(&workspace_path),
+        workspace_path,
+        workspace,
+        exported_at: chrono::Utc::now().timestamp_millis(),
+    };
+    
+    let content = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+    fs::write(&output_path, content).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[tauri::command]
+fn export_snapshots_csv(workspace_path: String, output_path: String) -> Result<usize, String> {
+    let mut snapshots = load_workspace_index(&workspace_path).snapshots;
+    snapshots.sort_by_key(|s| s.timestamp);
+    
+    let mut csv = String::from("id,timestamp,file_count,total_size,message\n");
+    for snapshot in &snapshots {
+        let timestamp = chrono::DateTime::from_timestamp_millis(snapshot.timestamp)
+            .map(|dt| dt.to_rfc3339())
+            .unwrap_or_default();
+        let total_size: u64 = snapshot.files.iter().map(|f| f.size).sum();
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            csv_escape(&snapshot.id),
+            timestamp,
+            snapshot.files.len(),
+            total_size,
+            csv_escape(snapshot.message.as_deref().unwrap_or(""))
+        ));
+    }
+    
+    fs::write(&output_path, csv).map_err(|e| e.to_string())?;
+    Ok(snapshots.len())
+}
+
+#[tauri::command]
+fn import_snapshot(workspace_path: String, archive_path: String) -> Result<String, String> {
+    let archive_file = fs::File::open(&archive_path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(archive_file).map_err(|e| e.to_string())?;
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    fs::create_dir_all(&snapshots_dir).map_err(|e| e.to_string())?;
+    
+    let index = load_workspace_index(&workspace_path);
+    let mut timestamp = chrono::Utc::now().timestamp_millis();
+    while index.snapshots.iter().any(|s| s.timestamp == timestamp) {
+        timestamp += 1;
+    }
+    let snapshot_id = format!("snap_{}", timestamp);
+    
+    let mut files = vec![];
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        if entry.is_dir() {
+            continue;
+        }
+        
+        // Entries with absolute or parent-relative names would escape the workspace
+        let relative_path = match entry.enclosed_name() {
+            Some(name) => name.to_string_lossy().replace('\\', "/"),
+            None => continue,
+        };
+        if relative_path == SHIELD_DIR || relative_path.starts_with(&format!("{}/", SHIELD_DIR)) {
+            continue;
+        }
+        
+        let backup_path = backup_name_for(timestamp, &relative_path);
+        let backup_full_path = snapshots_dir.join(&backup_path);
+        let mut backup_file = fs::File::create(&backup_full_path).map_err(|e| e.to_string())?;
+        let size = std::io::copy(&mut entry, &mut backup_file).map_err(|e| e.to_string())?;
+        drop(backup_file);
+        
+        files.push(SnapshotFile {
+            path: relative_path,
+            backup_path,
+            size,
+            event_type: "change".to_string(),
+            renamed_to: None,
+            hash: hash_file(&backup_full_path),
+            codec: None,
+        });
+    }
+    
+    if files.is_empty() {
+        return Err("Archive contains no files to import".to_string());
+    }
+    
+    let archive_name = PathBuf::from(&archive_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("archive")
+        .to_string();
+    
+    let snapshot = Snapshot {
+        id: snapshot_id.clone(),
+        timestamp,
+        files,
+        message: Some(format!("Imported from {}", archive_name)),
+        pinned: false,
+        trigger: Some("import".to_string()),
+    };
+    update_workspace_index(&workspace_path, |index| {
+        index.snapshots.push(snapshot);
+        Ok(())
+    })?;
+    
+    Ok(snapshot_id)
+}
+
+/// Packs `index.json` and every backup blob it references into one zip for cold storage.
+#[tauri::command(async)]
+fn export_full_history(workspace_path: String, output_path: String) -> Result<u64, String> {
+    let index = load_workspace_index(&workspace_path);
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    
+    let output = fs::File::create(&output_path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipWriter::new(output);
+    let deflated = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    // Gzipped blobs would not shrink further, so store them as-is
+    let stored = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    
+    archive.start_file(INDEX_FILE, deflated).map_err(|e| e.to_string())?;
+    let index_json = serde_json::to_vec_pretty(&index).map_err(|e| e.to_string())?;
+    std::io::Write::write_all(&mut archive, &index_json).map_err(|e| e.to_string())?;
+    
+    let mut seen = std::collections::HashSet::new();
+    for file in index.snapshots.iter().flat_map(|s| s.files.iter()) {
+        if file.backup_path.is_empty() || !seen.insert(file.backup_path.as_str()) {
+            continue;
+        }
+        let Ok(mut blob) = fs::File::open(snapshots_dir.join(&file.backup_path)) else {
+            continue;
+        };
+        let options = if file.codec.is_some() { stored } else { deflated };
+        archive
+            .start_file(format!("{}/{}", SNAPSHOTS_DIR, file.backup_path.replace('\\', "/")), options)
+            .map_err(|e| e.to_string())?;
+        std::io::copy(&mut blob, &mut archive).map_err(|e| e.to_string())?;
+    }
+    
+    archive.finish().map_err(|e| e.to_string())?;
+    fs::metadata(&output_path).map(|m| m.len()).map_err(|e| e.to_string())
+}
+
+/// Restores an `export_full_history` archive into a workspace that has no history of its own yet.
+#[tauri::command(async)]
+fn import_full_history(workspace_path: String, archive_path: String) -> Result<usize, String> {
+    if count_workspace_snapshots(&workspace_path) > 0 {
+        return Err("Workspace already has snapshots; import full history into a new workspace".to_string());
+    }
+    
+    let archive_file = fs::File::open(&archive_path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(archive_file).map_err(|e| e.to_string())?;
+    
+    let imported: BackupIndex = {
+        let entry = archive
+            .by_name(INDEX_FILE)
+            .map_err(|_| format!("Archive has no {}", INDEX_FILE))?;
+        serde_json::from_reader(std::io::BufReader::new(entry)).map_err(|e| format!("Archive index is invalid: {}", e))?
+    };
+    if imported.version > SUPPORTED_INDEX_VERSION {
+        return Err(newer_index_error(imported.version));
+    }
+    
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    fs::create_dir_all(&snapshots_dir).map_err(|e| e.to_string())?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        if entry.is_dir() {
+            continue;
+        }
+        // Entries with absolute or parent-relative names would escape the snapshots directory
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+        let Ok(relative) = name.strip_prefix(SNAPSHOTS_DIR) else {
+            continue;
+        };
+        let target = snapshots_dir.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut blob = fs::File::create(&target).map_err(|e| e.to_string())?;
+        std::io::copy(&mut entry, &mut blob).map_err(|e| e.to_string())?;
+    }
+    
+    let count = imported.snapshots.len();
+    update_workspace_index(&workspace_path, |index| {
+        *index = imported;
+        Ok(())
+    })?;
+    
+    Ok(count)
+}
+
+#[tauri::command]
+fn begin_operation(operations: State<'_, OperationRegistry>) -> String {
+    operations.begin()
+}
+
+#[tauri::command]
+fn cancel_operation(operations: State<'_, OperationRegistry>, op_id: String) -> bool {
+    operations.cancel(&op_id)
+}
+
+#[tauri::command]
+fn watch_workspace_index(
+    app: AppHandle,
+    watchers: State<'_, IndexWatchers>,
+    workspace_path: String,
+) -> Result<(), String> {
+    use notify::Watcher;
+    
+    let mut watchers = watchers.watchers.lock().unwrap();
+    if watchers.contains_key(&workspace_path) {
+        return Ok(());
+    }
+    
+    let shield_dir = get_shield_dir(&workspace_path);
+    fs::create_dir_all(&shield_dir).map_err(|e| e.to_string())?;
+    
+    let debounce = std::time::Duration::from_millis(
+        load_global_config().watch_debounce_ms.unwrap_or(DEFAULT_WATCH_DEBOUNCE_MS),
+    );
+    let index_file = load_workspace_layout(&workspace_path).index_file;
+    let (tx, rx) = std::sync::mpsc::channel::<()>();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if event.paths.iter().any(|p| p.ends_with(&index_file)) {
+                tx.send(()).ok();
+            }
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    watcher
+        .watch(&shield_dir, notify::RecursiveMode::NonRecursive)
+        .map_err(|e| e.to_string())?;
+    
+    let path = workspace_path.clone();
+    std::thread::spawn(move || {
+        // Each burst of index writes produces one event once it has been quiet for `debounce`
+        while rx.recv().is_ok() {
+            loop {
+                match rx.recv_timeout(debounce) {
+                    Ok(_) => continue,
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => break,
+                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            app.emit("workspace-index-changed", &path).ok();
+        }
+    });
+    
+    watchers.insert(workspace_path, watcher);
+    Ok(())
+}
+
+#[tauri::command]
+fn unwatch_workspace_index(watchers: State<'_, IndexWatchers>, workspace_path: String) {
+    watchers.watchers.lock().unwrap().remove(&workspace_path);
+}
+
+#[tauri::command]
+fn start_log_stream(app: AppHandle, streams: State<'_, LogStreams>, workspace_path: String) -> Result<(), String> {
+    use notify::Watcher;
+    use std::io::{Read, Seek};
+    
+    let mut streams = streams.watchers.lock().unwrap();
+    if streams.contains_key(&workspace_path) {
+        return Ok(());
+    }
+    
+    let shield_dir = get_shield_dir(&workspace_path);
+    fs::create_dir_all(&shield_dir).map_err(|e| e.to_string())?;
+    
+    // Watch the directory so streaming works even before the daemon creates the log
+    let watched = get_log_file_path(&workspace_path);
+    let (tx, rx) = std::sync::mpsc::channel::<()>();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if event.paths.iter().any(|p| p.ends_with(LOG_FILE)) {
+                tx.send(()).ok();
+            }
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    watcher
+        .watch(&shield_dir, notify::RecursiveMode::NonRecursive)
+        .map_err(|e| e.to_string())?;
+    
+    let path = workspace_path.clone();
+    std::thread::spawn(move || {
+        // Only lines appended after the stream starts are emitted; earlier history comes from the log tail
+        let mut offset = fs::metadata(&watched).map(|m| m.len()).unwrap_or(0);
+        let mut partial = String::new();
+        while rx.recv().is_ok() {
+            let Ok(mut file) = fs::File::open(&watched) else {
+                continue;
+            };
+            let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+            if len < offset {
+                // Truncated or rotated; start over from the top of the new file
+                offset = 0;
+                partial.clear();
+            }
+            if len == offset || file.seek(std::io::SeekFrom::Start(offset)).is_err() {
+                continue;
+            }
+            let mut appended = vec![];
+            let Ok(read) = file.read_to_end(&mut appended) else {
+                continue;
+            };
+            offset += read as u64;
+            partial.push_str(&String::from_utf8_lossy(&appended));
+            
+            // Hold back a trailing fragment until the daemon finishes writing the line
+            while let Some(end) = partial.find('\n') {
+                let line = partial[..end].trim_end_matches('\r').to_string();
+                partial.drain(..=end);
+                app.emit(
+                    "shield-log-line",
+                    ShieldLogLine {
+                        workspace_path: path.clone(),
+                        line,
+                    },
+                )
+                .ok();
+            }
+        }
+    });
+    
+    streams.insert(workspace_path, watcher);
+    Ok(())
+}
+
+#[tauri::command]
+fn stop_log_stream(streams: State<'_, LogStreams>, workspace_path: String) {
+    streams.watchers.lock().unwrap().remove(&workspace_path);
+}
+
+#[tauri::command]
+fn set_watch_debounce_ms(debounce_ms: Option<u64>) -> Result<(), String> {
+    let mut config = load_global_config();
+    config.watch_debounce_ms = debounce_ms;
+    save_global_config(&config)
+}
+
+#[tauri::command]
+fn set_restore_no_delete(no_delete: Option<bool>) -> Result<(), String> {
+    let mut config = load_global_config();
+    config.restore_no_delete = no_delete;
+    save_global_config(&config)
+}
+
+#[tauri::command(async)]
+fn get_shield_status(launched: State<'_, LaunchedPids>, workspace_path: String) -> ShieldStatus {
+    detect_shield_status(&launched, &workspace_path)
+}
+
+#[tauri::command]
+fn get_daemon_watch_root(launched: State<'_, LaunchedPids>, workspace_path: String) -> Result<String, String> {
+    let status = detect_shield_status(&launched, &workspace_path);
+    let pid = match status.pid {
+        Some(pid) if status.running => pid,
+        _ => return Err("Shield is not running".to_string()),
+    };
+    let cmdline = list_processes()
+        .into_iter()
+        .find(|(p, _)| *p == pid)
+        .map(|(_, cmdline)| cmdline)
+        .ok_or_else(|| format!("Could not read the command line of PID {}", pid))?;
+    
+    daemon_watch_root_from_cmdline(&cmdline)
+        .ok_or_else(|| format!("Could not find the watched directory in: {}", cmdline))
+}
+
+// The CLI daemonizes as `<node> <script> watch --daemon --log-file=<vault>/shield.log <workspace>`.
+// `ps` drops quoting, so the log file name marks where its value ends even when paths contain spaces.
+fn daemon_watch_root_from_cmdline(cmdline: &str) -> Option<String> {
+    let (_, mut rest) = cmdline.split_once(" watch ")?;
+    loop {
+        rest = rest.trim_start().trim_start_matches('"');
+        if !rest.starts_with("--") {
+            break;
+        }
+        let end = if rest.starts_with("--log-file=") {
+            rest.find(LOG_FILE).map(|i| i + LOG_FILE.len())?
+        } else {
+            rest.find(char::is_whitespace).unwrap_or(rest.len())
+        };
+        rest = rest[end..].trim_start_matches('"');
+    }
+    let root = rest.trim().trim_matches('"');
+    (!root.is_empty()).then(|| root.to_string())
+}
+
+#[tauri::command]
+fn get_all_shield_statuses(launched: State<'_, LaunchedPids>) -> Vec<(String, ShieldStatus)> {
+    let mut processes = None;
+    load_global_config()
+        .workspaces
+        .into_iter()
+        .map(|w| {
+            let status = detect_shield_status_with(&launched, &w.path, &mut processes);
+            (w.path, status)
+        })
+        .collect()
 }
 
-#[tauri::command]
-fn restore_snapshot(workspace_path: String, snapshot_id: String) -> Result<RestoreResult, String> {
-    let index = load_workspace_index(&workspace_path);
-    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
-    let restore_lock = get_restore_lock_path(&workspace_path);
+#[tauri::command(async)]
+fn ping_shield(workspace_path: String) -> Result<bool, String> {
+    if !check_shield_running(&workspace_path).running {
+        return Err("Shield is not running".to_string());
+    }
     
-    // Create restore lock to prevent watcher from recording changes
-    fs::write(&restore_lock, format!("{}", chrono::Utc::now().timestamp_millis())).ok();
+    let started = chrono::Utc::now().timestamp_millis();
+    let ping_name = format!("agentshield-ping-{}.txt", started);
+    let ping_path = PathBuf::from(&workspace_path).join(&ping_name);
     
-    let snapshot = index
-        .snapshots
-        .iter()
-        .find(|s| s.id == snapshot_id)
-        .ok_or("Snapshot not found")?;
+    fs::write(&ping_path, format!("{}", started)).map_err(|e| e.to_string())?;
     
-    let mut restored = 0u32;
-    let mut failed = 0u32;
-    let mut deleted = 0u32;
+    // The watcher debounces and then batches changes, so allow a few seconds for the snapshot to land.
+    // Only a snapshot holding the probe counts; log activity alone may be unrelated to it.
+    let deadline = std::time::Instant::now() + std::time::Duration::from_millis(PING_TIMEOUT_MS);
+    let mut responsive = false;
+    while std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(250));
+        responsive = load_workspace_index(&workspace_path)
+            .snapshots
+            .iter()
+            .any(|s| s.timestamp >= started && s.files.iter().any(|f| f.path == ping_name));
+        if responsive {
+            break;
+        }
+    }
     
-    for file in &snapshot.files {
-        let backup_full_path = snapshots_dir.join(&file.backup_path);
-        let target_path = PathBuf::from(&workspace_path).join(&file.path);
-        
-        match file.event_type.as_str() {
-            "delete" => {
-                if backup_full_path.exists() {
-                    if let Some(parent) = target_path.parent() {
-                        fs::create_dir_all(parent).ok();
-                    }
-                    if fs::copy(&backup_full_path, &target_path).is_ok() {
-                        restored += 1;
-                    } else {
-                        failed += 1;
-                    }
-                } else {
-                    failed += 1;
-                }
-            }
-            "rename" => {
-                if let Some(renamed_to) = &file.renamed_to {
-                    let renamed_path = PathBuf::from(&workspace_path).join(renamed_to);
-                    if renamed_path.exists() {
-                        if fs::remove_file(&renamed_path).is_ok() {
-                            deleted += 1;
-                        }
-                    }
-                }
-                if backup_full_path.exists() {
-                    if let Some(parent) = target_path.parent() {
-                        fs::create_dir_all(parent).ok();
-                    }
-                    if fs::copy(&backup_full_path, &target_path).is_ok() {
-                        restored += 1;
-                    } else {
-                        failed += 1;
-                    }
-                } else {
-                    failed += 1;
-                }
-            }
-            "create" => {
-                if target_path.exists() {
-                    if fs::remove_file(&target_path).is_ok() {
-                        deleted += 1;
-                    }
-                }
-            }
-            "change" => {
-                if backup_full_path.exists() {
-                    if let Some(parent) = target_path.parent() {
-                        fs::create_dir_all(parent).ok();
-                    }
-                    if fs::copy(&backup_full_path, &target_path).is_ok() {
-                        restored += 1;
-                    } else {
-                        failed += 1;
-                    }
-                } else {
-                    failed += 1;
+    // Remove the probe under the restore lock so the daemon doesn't record its deletion,
+    // then drop its entries and blobs so the ping leaves no trace in history
+    let restore_lock = get_restore_lock_path(&workspace_path);
+    fs::write(&restore_lock, format!("{}", chrono::Utc::now().timestamp_millis())).ok();
+    fs::remove_file(&ping_path).ok();
+    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
+    update_workspace_index(&workspace_path, |index| {
+        let mut probe_blobs = vec![];
+        for snapshot in &mut index.snapshots {
+            snapshot.files.retain(|f| {
+                let is_probe = f.path == ping_name;
+                if is_probe && !f.backup_path.is_empty() {
+                    probe_blobs.push(f.backup_path.clone());
                 }
+                !is_probe
+            });
+        }
+        index.snapshots.retain(|s| s.timestamp < started || !s.files.is_empty());
+        for blob in probe_blobs {
+            let still_referenced = index.snapshots.iter().any(|s| s.files.iter().any(|f| f.backup_path == blob));
+            if !still_referenced {
+                fs::remove_file(snapshots_dir.join(&blob)).ok();
             }
-            _ => {}
         }
-    }
-    
-    // Schedule lock removal after watcher debounce window
-    let lock_path = restore_lock.clone();
+        Ok(())
+    })
+    .ok();
     std::thread::spawn(move || {
         std::thread::sleep(std::time::Duration::from_millis(3500));
-        fs::remove_file(&lock_path).ok();
+        fs::remove_file(&restore_lock).ok();
     });
     
-    Ok(RestoreResult {
-        restored,
-        failed,
-        deleted,
-    })
+    Ok(responsive)
 }
 
 #[tauri::command]
-fn clean_old_snapshots(workspace_path: String, max_age_days: i64) -> Result<(usize, u64), String> {
-    let mut index = load_workspace_index(&workspace_path);
-    let snapshots_dir = get_workspace_snapshots_dir(&workspace_path);
-    let cutoff = chrono::Utc::now().timestamp_millis() - (max_age_days * 24 * 60 * 60 * 1000);
-    
-    let mut removed = 0usize;
-    let mut freed_bytes = 0u64;
-    
-    let mut to_keep = vec![];
+fn reconcile_shield_pid(launched: State<'_, LaunchedPids>, workspace_path: String) -> CommandResult {
+    let status = detect_shield_status(&launched, &workspace_path);
+    if !status.orphaned {
+        return CommandResult {
+            success: true,
+            message: "PID file is already consistent".to_string(),
+        };
+    }
     
-    for snapshot in index.snapshots {
-        if snapshot.timestamp < cutoff {
-            for file in &snapshot.files {
-                let backup_path = snapshots_dir.join(&file.backup_path);
-                if backup_path.exists() {
-                    if let Ok(meta) = fs::metadata(&backup_path) {
-                        freed_bytes += meta.len();
-                    }
-                    fs::remove_file(&backup_path).ok();
-                }
-            }
-            removed += 1;
-        } else {
-            to_keep.push(snapshot);
+    let pid = status.pid.unwrap_or(0);
+    match fs::write(get_pid_file_path(&workspace_path), pid.to_string()) {
+        Ok(_) => CommandResult {
+            success: true,
+            message: format!("PID file restored for running shield (PID: {})", pid),
+        },
+        Err(e) => CommandResult {
+            success: false,
+            message: format!("Failed to write PID file: {}", e),
+        },
+    }
+}
+
+const SHELL_METACHARACTERS: &[char] = &[
+    ';', '&', '|', '$', '`', '<', '>', '(', ')', '{', '}', '*', '?', '!', '\\', '"', '\'', '\n', '\r',
+];
+
+fn validate_daemon_args(args: &[String]) -> Result<(), String> {
+    for arg in args {
+        if arg.trim().is_empty() {
+            return Err("Daemon arguments must not be empty".to_string());
+        }
+        if arg.contains(SHELL_METACHARACTERS) {
+            return Err(format!("Invalid characters in daemon argument: {}", arg));
         }
     }
-    
-    index.snapshots = to_keep;
-    save_workspace_index(&workspace_path, &index)?;
-    
-    Ok((removed, freed_bytes))
+    Ok(())
 }
 
-#[tauri::command]
-fn get_shield_status(workspace_path: String) -> ShieldStatus {
-    check_shield_running(&workspace_path)
+// Variables that would change which code the daemon loads or how it resolves binaries
+const PROTECTED_ENV_VARS: &[&str] = &["PATH", "NODE_OPTIONS", "NODE_PATH", "LD_PRELOAD", "LD_LIBRARY_PATH"];
+
+fn validate_daemon_env(env: &HashMap<String, String>) -> Result<(), String> {
+    for (key, value) in env {
+        let valid_key = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_key {
+            return Err(format!("Invalid environment variable name: {}", key));
+        }
+        let upper = key.to_ascii_uppercase();
+        if PROTECTED_ENV_VARS.contains(&upper.as_str()) || upper.starts_with("DYLD_") {
+            return Err(format!("Environment variable {} cannot be overridden", key));
+        }
+        if value.contains(['\0', '\n', '\r']) {
+            return Err(format!("Invalid characters in value of {}", key));
+        }
+    }
+    Ok(())
 }
 
 #[tauri::command]
-fn start_shield(workspace_path: String) -> CommandResult {
-    let shield_bin = match find_shield_binary() {
+fn set_workspace_env(path: String, env: HashMap<String, String>) -> Result<Workspace, String> {
+    validate_daemon_env(&env)?;
+    
+    let mut config = load_global_config();
+    let workspace = config
+        .workspaces
+        .iter_mut()
+        .find(|w| w.path == path)
+        .ok_or("Workspace not found")?;
+    
+    workspace.env = env;
+    let updated = workspace.clone();
+    save_global_config(&config)?;
+    
+    Ok(updated)
+}
+
+#[tauri::command(async)]
+fn start_shield(
+    shield_binary: State<'_, ShieldBinaryCache>,
+    launched: State<'_, LaunchedPids>,
+    workspace_path: String,
+    args: Option<Vec<String>>,
+) -> CommandResult {
+    let args = args.unwrap_or_default();
+    if let Err(e) = validate_daemon_args(&args) {
+        return CommandResult {
+            success: false,
+            message: e,
+        };
+    }
+    
+    // The config file can be edited by hand, so check the stored env again before using it
+    let env = load_global_config()
+        .workspaces
+        .into_iter()
+        .find(|w| w.path == workspace_path)
+        .map(|w| w.env)
+        .unwrap_or_default();
+    if let Err(e) = validate_daemon_env(&env) {
+        return CommandResult {
+            success: false,
+            message: e,
+        };
+    }
+    
+    let shield_bin = match resolve_shield_binary(&shield_binary) {
         Some(path) => path,
         None => {
             return CommandResult {
@@ -503,7 +4467,7 @@ fn start_shield(workspace_path: String) -> CommandResult {
         }
     };
     
-    let status = check_shield_running(&workspace_path);
+    let status = detect_shield_status(&launched, &workspace_path);
     if status.running {
         return CommandResult {
             success: true,
@@ -511,47 +4475,154 @@ fn start_shield(workspace_path: String) -> CommandResult {
         };
     }
     
-    let output = Command::new(&shield_bin)
+    let log_path = get_log_file_path(&workspace_path);
+    let mut command = Command::new(&shield_bin);
+    command
         .arg("start")
         .arg(&workspace_path)
-        .current_dir(&workspace_path)
-        .output();
+        .args(&args)
+        .envs(&env)
+        .current_dir(&workspace_path);
     
-    match output {
-        Ok(result) => {
-            let stdout = String::from_utf8_lossy(&result.stdout);
-            let stderr = String::from_utf8_lossy(&result.stderr);
+    let mut child = match spawn_detached(&mut command, &log_path) {
+        Ok(child) => child,
+        Err(e) => {
+            return CommandResult {
+                success: false,
+                message: format!("Failed to execute shield command: {}", e),
+            };
+        }
+    };
+    
+    // `shield start` normally forks the watcher and exits; if it is still alive after the wait it is the daemon itself
+    launched.track(child.id());
+    let deadline = std::time::Instant::now() + std::time::Duration::from_millis(DAEMON_FORK_WAIT_MS);
+    let exit_status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if std::time::Instant::now() < deadline => {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+            _ => break None,
+        }
+    };
+    launched.untrack(child.id());
+    
+    match exit_status {
+        Some(status) if status.success() => {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            let new_status = check_shield_running(&workspace_path);
+            CommandResult {
+                success: true,
+                message: if new_status.running {
+                    format!("Shield started successfully (PID: {})", new_status.pid.unwrap_or(0))
+                } else {
+                    format!("Shield start command completed. {}", read_log_tail(&log_path, 5).trim())
+                },
+            }
+        }
+        Some(_) => CommandResult {
+            success: false,
+            message: format!("Failed to start shield: {}", read_log_tail(&log_path, 20)),
+        },
+        None => {
+            let pid = child.id();
+            let pid_path = get_pid_file_path(&workspace_path);
+            if !pid_path.exists() {
+                fs::write(&pid_path, pid.to_string()).ok();
+            }
+            // Reap the foreground daemon when it eventually exits so it doesn't linger as a zombie
+            std::thread::spawn(move || {
+                child.wait().ok();
+            });
             
-            if result.status.success() {
-                std::thread::sleep(std::time::Duration::from_millis(500));
-                let new_status = check_shield_running(&workspace_path);
+            let new_status = check_shield_running(&workspace_path);
+            if new_status.running {
                 CommandResult {
                     success: true,
-                    message: if new_status.running {
-                        format!("Shield started successfully (PID: {})", new_status.pid.unwrap_or(0))
-                    } else {
-                        format!("Shield start command completed. {}", stdout.trim())
-                    },
+                    message: format!("Shield started successfully (PID: {})", new_status.pid.unwrap_or(pid)),
                 }
             } else {
                 CommandResult {
                     success: false,
-                    message: format!("Failed to start shield: {}{}", stdout, stderr),
+                    message: format!("Shield process {} did not register in the PID file", pid),
                 }
             }
         }
-        Err(e) => CommandResult {
-            success: false,
-            message: format!("Failed to execute shield command: {}", e),
-        },
     }
 }
 
-#[tauri::command]
-fn stop_shield(workspace_path: String) -> CommandResult {
-    let shield_bin = match find_shield_binary() {
+fn spawn_detached(command: &mut Command, log_path: &Path) -> std::io::Result<std::process::Child> {
+    if let Some(parent) = log_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let log = fs::OpenOptions::new().create(true).append(true).open(log_path)?;
+    command
+        .stdin(std::process::Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+    
+    command.spawn()
+}
+
+fn read_log_tail(log_path: &Path, lines: usize) -> String {
+    let content = fs::read_to_string(log_path).unwrap_or_default();
+    let all: Vec<&str> = content.lines().collect();
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+#[tauri::command(async)]
+fn stop_shield(
+    shield_binary: State<'_, ShieldBinaryCache>,
+    launched: State<'_, LaunchedPids>,
+    workspace_path: String,
+    force: Option<bool>,
+    quiet_secs: Option<u64>,
+    wait_for_idle: Option<bool>,
+) -> CommandResult {
+    let force = force.unwrap_or(false);
+    let mut quiet_note = String::new();
+    if let Some(quiet_secs) = quiet_secs {
+        match wait_for_quiet_index(&workspace_path, quiet_secs, wait_for_idle.unwrap_or(false)) {
+            Ok(0) => quiet_note = "Stopped immediately. ".to_string(),
+            Ok(waited_ms) => quiet_note = format!("Waited {}ms for the workspace to go idle. ", waited_ms),
+            Err(message) => {
+                return CommandResult {
+                    success: false,
+                    message,
+                }
+            }
+        }
+    }
+    let shield_bin = match resolve_shield_binary(&shield_binary) {
         Some(path) => path,
         None => {
+            if force {
+                let status = check_shield_running(&workspace_path);
+                if let Some(pid) = status.pid {
+                    return force_stop_shield(&workspace_path, pid);
+                }
+            }
             return CommandResult {
                 success: false,
                 message: "Shield binary not found".to_string(),
@@ -567,11 +4638,120 @@ fn stop_shield(workspace_path: String) -> CommandResult {
         };
     }
     
-    let output = Command::new(&shield_bin)
-        .arg("stop")
-        .arg(&workspace_path)
-        .current_dir(&workspace_path)
-        .output();
+    let output = launched.output_within(
+        Command::new(&shield_bin)
+            .arg("stop")
+            .arg(&workspace_path)
+            .current_dir(&workspace_path),
+        GRACEFUL_STOP_TIMEOUT_MS,
+    );
+    
+    let failure = match output {
+        Ok(None) => format!("Shield stop command did not finish within {}ms", GRACEFUL_STOP_TIMEOUT_MS),
+        Ok(Some(result)) => {
+            let stdout = String::from_utf8_lossy(&result.stdout);
+            let stderr = String::from_utf8_lossy(&result.stderr);
+            
+            if result.status.success() {
+                std::thread::sleep(std::time::Duration::from_millis(300));
+                if !force || !check_shield_running(&workspace_path).running {
+                    return CommandResult {
+                        success: true,
+                        message: format!("{}Shield stopped gracefully. {}", quiet_note, stdout.trim()),
+                    };
+                }
+                format!("Shield did not exit after stop. {}", stdout.trim())
+            } else {
+                format!("Failed to stop shield: {}{}", stdout, stderr)
+            }
+        }
+        Err(e) => format!("Failed to execute shield command: {}", e),
+    };
+    
+    if force {
+        if let Some(pid) = status.pid {
+            return force_stop_shield(&workspace_path, pid);
+        }
+    }
+    
+    CommandResult {
+        success: false,
+        message: failure,
+    }
+}
+
+fn index_idle_ms(workspace_path: &str) -> Option<u128> {
+    let modified = fs::metadata(get_workspace_index_path(workspace_path)).and_then(|m| m.modified()).ok()?;
+    Some(modified.elapsed().map(|d| d.as_millis()).unwrap_or(0))
+}
+
+// Returns how long we waited for the index to sit untouched for `quiet_secs`
+fn wait_for_quiet_index(workspace_path: &str, quiet_secs: u64, wait: bool) -> Result<u64, String> {
+    let quiet_ms = quiet_secs as u128 * 1000;
+    let mut waited_ms = 0u64;
+    loop {
+        let idle_ms = match index_idle_ms(workspace_path) {
+            Some(idle_ms) if idle_ms < quiet_ms => idle_ms,
+            _ => return Ok(waited_ms),
+        };
+        if !wait {
+            return Err(format!(
+                "A snapshot was written {}ms ago; shield was not stopped to avoid interrupting a backup",
+                idle_ms
+            ));
+        }
+        if waited_ms >= QUIET_PERIOD_MAX_WAIT_MS {
+            return Err(format!(
+                "Workspace did not go idle for {}s within {}ms; shield was not stopped",
+                quiet_secs, QUIET_PERIOD_MAX_WAIT_MS
+            ));
+        }
+        let step_ms = ((quiet_ms - idle_ms) as u64).min(500);
+        std::thread::sleep(std::time::Duration::from_millis(step_ms));
+        waited_ms += step_ms;
+    }
+}
+
+fn force_stop_shield(workspace_path: &str, pid: u32) -> CommandResult {
+    if !kill_process(pid) && is_process_running(pid) {
+        return CommandResult {
+            success: false,
+            message: format!("Failed to force-kill shield (PID: {})", pid),
+        };
+    }
+    
+    fs::remove_file(get_pid_file_path(workspace_path)).ok();
+    
+    CommandResult {
+        success: true,
+        message: format!("Shield force-stopped (PID: {})", pid),
+    }
+}
+
+#[tauri::command]
+fn restore_snapshot_cmd(
+    shield_binary: State<'_, ShieldBinaryCache>,
+    launched: State<'_, LaunchedPids>,
+    workspace_path: String,
+    snapshot_id: String,
+) -> CommandResult {
+    let shield_bin = match resolve_shield_binary(&shield_binary) {
+        Some(path) => path,
+        None => {
+            return CommandResult {
+                success: false,
+                message: "Shield binary not found".to_string(),
+            };
+        }
+    };
+    
+    let output = launched.output(
+        Command::new(&shield_bin)
+            .arg("restore")
+            .arg(&snapshot_id)
+            .arg(format!("--path={}", workspace_path))
+            .current_dir(&workspace_path),
+    );
     
     match output {
         Ok(result) => {
@@ -579,28 +4759,32 @@ fn stop_shield(workspace_path: String) -> CommandResult {
             let stderr = String::from_utf8_lossy(&result.stderr);
             
             if result.status.success() {
-                std::thread::sleep(std::time::Duration::from_millis(300));
                 CommandResult {
                     success: true,
-                    message: format!("Shield stopped successfully. {}", stdout.trim()),
+                    message: stdout.trim().to_string(),
                 }
             } else {
                 CommandResult {
                     success: false,
-                    message: format!("Failed to stop shield: {}{}", stdout, stderr),
+                    message: format!("{}{}", stdout, stderr).trim().to_string(),
                 }
             }
         }
         Err(e) => CommandResult {
             success: false,
-            message: format!("Failed to execute shield command: {}", e),
+            message: format!("Failed to execute restore command: {}", e),
         },
     }
 }
 
 #[tauri::command]
-fn restore_snapshot_cmd(workspace_path: String, snapshot_id: String) -> CommandResult {
-    let shield_bin = match find_shield_binary() {
+fn create_snapshot(
+    shield_binary: State<'_, ShieldBinaryCache>,
+    launched: State<'_, LaunchedPids>,
+    workspace_path: String,
+    message: Option<String>,
+) -> CommandResult {
+    let shield_bin = match resolve_shield_binary(&shield_binary) {
         Some(path) => path,
         None => {
             return CommandResult {
@@ -610,22 +4794,42 @@ fn restore_snapshot_cmd(workspace_path: String, snapshot_id: String) -> CommandR
         }
     };
     
-    let output = Command::new(&shield_bin)
-        .arg("restore")
-        .arg(&snapshot_id)
+    let existing: std::collections::HashSet<String> = load_workspace_index(&workspace_path)
+        .snapshots
+        .into_iter()
+        .map(|s| s.id)
+        .collect();
+    
+    let mut command = Command::new(&shield_bin);
+    command
+        .arg("snapshot")
         .arg(format!("--path={}", workspace_path))
-        .current_dir(&workspace_path)
-        .output();
+        .current_dir(&workspace_path);
+    if let Some(message) = message.as_ref().filter(|m| !m.trim().is_empty()) {
+        command.arg(format!("--message={}", message));
+    }
     
-    match output {
+    match launched.output(&mut command) {
         Ok(result) => {
             let stdout = String::from_utf8_lossy(&result.stdout);
             let stderr = String::from_utf8_lossy(&result.stderr);
             
             if result.status.success() {
-                CommandResult {
-                    success: true,
-                    message: stdout.trim().to_string(),
+                // A zero exit alone proves nothing (an unknown subcommand prints help and exits 0)
+                let new_snapshot = load_workspace_index(&workspace_path)
+                    .snapshots
+                    .into_iter()
+                    .filter(|s| !existing.contains(&s.id))
+                    .max_by_key(|s| s.timestamp);
+                match new_snapshot {
+                    Some(snapshot) => CommandResult {
+                        success: true,
+                        message: snapshot.id,
+                    },
+                    None => CommandResult {
+                        success: false,
+                        message: format!("Shield did not record a snapshot. {}", stdout.trim()),
+                    },
                 }
             } else {
                 CommandResult {
@@ -636,29 +4840,181 @@ fn restore_snapshot_cmd(workspace_path: String, snapshot_id: String) -> CommandR
         }
         Err(e) => CommandResult {
             success: false,
-            message: format!("Failed to execute restore command: {}", e),
+            message: format!("Failed to execute snapshot command: {}", e),
         },
     }
 }
 
+#[tauri::command]
+fn refresh_shield_binary(shield_binary: State<'_, ShieldBinaryCache>) -> Option<String> {
+    shield_binary.path.lock().unwrap().take();
+    resolve_shield_binary(&shield_binary).map(|path| path.to_string_lossy().to_string())
+}
+
+fn spawn_shield_status_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut last: HashMap<String, (bool, Option<u32>)> = HashMap::new();
+        loop {
+            let mut current = HashMap::new();
+            for workspace in load_global_config().workspaces {
+                let status = check_shield_running(&workspace.path);
+                let state = (status.running, status.pid);
+                // Newly seen workspaces only seed the state; the UI fetches their status on its own
+                if last.get(&workspace.path).is_some_and(|previous| *previous != state) {
+                    app.emit(
+                        "shield-status-changed",
+                        ShieldStatusChange {
+                            workspace_path: workspace.path.clone(),
+                            running: status.running,
+                            pid: status.pid,
+                        },
+                    )
+                    .ok();
+                }
+                current.insert(workspace.path, state);
+            }
+            last = current;
+            std::thread::sleep(std::time::Duration::from_millis(SHIELD_STATUS_POLL_MS));
+        }
+    });
+}
+
+fn spawn_auto_clean_scheduler(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        for workspace in load_global_config().workspaces {
+            let Some(days) = workspace.auto_clean_days else {
+                continue;
+            };
+            if !Path::new(&workspace.path).is_dir() {
+                continue;
+            }
+            
+            if let Ok((removed, freed_bytes)) = run_clean_old_snapshots(&workspace.path, days, &AtomicBool::new(false)) {
+                if removed > 0 {
+                    app.emit(
+                        "auto-clean-completed",
+                        AutoCleanSummary {
+                            workspace_path: workspace.path,
+                            removed,
+                            freed_bytes,
+                        },
+                    )
+                    .ok();
+                }
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_millis(AUTO_CLEAN_INTERVAL_MS));
+    });
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
+        .manage(OperationRegistry::default())
+        .manage(IndexWatchers::default())
+        .manage(LogStreams::default())
+        .manage(LaunchedPids::default())
+        .manage(ShieldBinaryCache::default())
+        .setup(|app| {
+            fs::remove_dir_all(get_extract_dir()).ok();
+            spawn_shield_status_monitor(app.handle().clone());
+            spawn_auto_clean_scheduler(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
+            format_relative_time,
+            format_bytes,
+            get_event_type_label,
             get_workspaces,
+            get_workspaces_by_activity,
             add_workspace,
+            is_workspace_managed,
             remove_workspace,
+            remove_workspaces,
+            rename_workspace_directory,
+            refresh_workspace_name,
+            clone_workspace_config,
+            update_workspace_appearance,
+            set_storage_budget,
+            set_auto_clean_days,
+            set_workspace_env,
+            get_effective_config,
+            get_workspace_paths,
             get_workspace_snapshots,
+            search_snapshots,
+            get_file_history,
+            latest_snapshot_for_file,
+            search_all_workspaces,
+            find_snapshot_workspace,
+            compare_workspaces,
+            get_coverage_gaps,
+            get_snapshot_sessions,
+            get_adjacent_snapshots,
+            get_snapshot_changes,
+            get_snapshot_size,
             get_workspace_stats,
+            get_index_version,
+            migrate_index,
+            get_global_stats,
+            get_last_activity,
+            get_snapshot_count,
+            get_unique_file_count,
             restore_snapshot,
+            restore_latest,
+            restore_at,
+            restore_merged,
+            get_last_restore,
+            get_operation_log,
+            preview_restore,
+            estimate_restore,
             clean_old_snapshots,
+            preview_clean_old_snapshots,
+            verify_workspace,
+            recompress_workspace,
+            prune_dangling_entries,
+            normalize_index_paths,
+            enforce_storage_budget,
+            preview_delete_snapshot,
+            get_snapshot_storage_breakdown,
+            set_snapshot_pinned,
+            merge_snapshots,
+            find_orphaned_backups,
+            snapshots_using_backup,
+            remove_orphaned_backups,
+            purge_workspace_history,
+            preview_purge_workspace_history,
+            move_workspace_storage,
+            export_workspace_index,
+            export_snapshots_csv,
+            import_snapshot,
+            export_full_history,
+            import_full_history,
+            diff_snapshot_to_current,
+            snapshot_as_patch,
+            get_backup_file_path,
+            extract_snapshot_file,
+            get_snapshot_image_preview,
+            begin_operation,
+            cancel_operation,
+            watch_workspace_index,
+            unwatch_workspace_index,
+            start_log_stream,
+            stop_log_stream,
+            set_watch_debounce_ms,
+            set_restore_no_delete,
             get_shield_status,
+            get_all_shield_statuses,
+            get_daemon_watch_root,
+            ping_shield,
+            reconcile_shield_pid,
             start_shield,
             stop_shield,
-            restore_snapshot_cmd
+            restore_snapshot_cmd,
+            create_snapshot,
+            refresh_shield_binary
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
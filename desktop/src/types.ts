@@ -17,6 +17,7 @@ export interface Snapshot {
   timestamp: number;
   files: SnapshotFile[];
   message?: string;
+  trigger?: string;
 }
 
 export interface WorkspaceStats {
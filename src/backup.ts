@@ -8,7 +8,8 @@ import {
   unlinkSync
 } from "fs";
 import { join, dirname } from "path";
-import { ShieldConfig, getSnapshotsDir, getIndexPath } from "./config.js";
+import { gunzipSync } from "zlib";
+import { ShieldConfig, getSnapshotsDir, getIndexPath, getIndexLockPath } from "./config.js";
 import { matchesPattern, removeEmptyDirs } from "./utils.js";
 import { 
   smartBackup, 
@@ -19,6 +20,35 @@ import {
   BackupStats
 } from "./hardlink.js";
 
+const INDEX_LOCK_TIMEOUT_MS = 5000;
+const INDEX_LOCK_STALE_MS = 30000;
+
+function sleepSync(ms: number): void {
+  Atomics.wait(new Int32Array(new SharedArrayBuffer(4)), 0, 0, ms);
+}
+
+function isProcessAlive(pid: number): boolean {
+  try {
+    process.kill(pid, 0);
+    return true;
+  } catch (err) {
+    return (err as NodeJS.ErrnoException).code === "EPERM";
+  }
+}
+
+function readBackup(backupFullPath: string, file: { codec?: string }): Buffer {
+  const raw = readFileSync(backupFullPath);
+  return file.codec === "gzip" ? gunzipSync(raw) : raw;
+}
+
+function copyBackup(backupFullPath: string, targetPath: string, file: { codec?: string }): void {
+  if (file.codec) {
+    writeFileSync(targetPath, readBackup(backupFullPath, file));
+  } else {
+    copyFileSync(backupFullPath, targetPath);
+  }
+}
+
 export type FileEventType = "change" | "delete" | "rename" | "create";
 
 // File record in snapshot
@@ -29,6 +59,7 @@ export interface SnapshotFile {
   eventType: FileEventType;
   renamedTo?: string;     // New path when renamed
   backupMethod?: BackupMethod; // How the file was backed up (hardlink or copy)
+  codec?: "gzip";         // Backup blob is compressed (set by desktop recompression)
 }
 
 // Snapshot - A version point on the timeline
@@ -37,6 +68,7 @@ export interface Snapshot {
   timestamp: number;      // Timestamp
   files: SnapshotFile[];  // List of changed files
   message?: string;       // Optional description
+  trigger?: string;       // What caused it: "watch", "manual", "pre-restore", ...
 }
 
 // Simplified index structure
@@ -49,12 +81,14 @@ export class BackupManager {
   private config: ShieldConfig;
   private snapshotsDir: string;
   private indexPath: string;
+  private indexLockPath: string;
   private index: BackupIndex;
 
   constructor(config: ShieldConfig) {
     this.config = config;
     this.snapshotsDir = getSnapshotsDir(config);
     this.indexPath = getIndexPath(config);
+    this.indexLockPath = getIndexLockPath(config);
     
     this.ensureVaultExists();
     this.index = this.loadIndex();
@@ -98,6 +132,79 @@ export class BackupManager {
     writeFileSync(this.indexPath, JSON.stringify(this.index, null, 2));
   }
 
+  /**
+   * Run a read-modify-write cycle on the index under the shared index lock.
+   *
+   * Protocol (shared with the desktop app): create index.lock exclusively containing
+   * {"pid", "timestamp"}, reload index.json, write it back, then delete the lock.
+   * A lock whose holder is dead or that is older than 30s is stale and may be removed.
+   * Writers give up waiting after 5s.
+   */
+  private withIndexLock(update: () => void): void {
+    const acquired = this.acquireIndexLock();
+    try {
+      this.index = this.loadIndex();
+      update();
+      this.saveIndex();
+    } finally {
+      if (acquired) {
+        try {
+          unlinkSync(this.indexLockPath);
+        } catch {
+          // ignore
+        }
+      }
+    }
+  }
+
+  private acquireIndexLock(): boolean {
+    const started = Date.now();
+    for (;;) {
+      try {
+        writeFileSync(
+          this.indexLockPath,
+          JSON.stringify({ pid: process.pid, timestamp: Date.now() }),
+          { flag: "wx" }
+        );
+        return true;
+      } catch (err) {
+        if ((err as NodeJS.ErrnoException).code !== "EEXIST") {
+          return false;
+        }
+      }
+
+      if (this.isIndexLockStale()) {
+        try {
+          unlinkSync(this.indexLockPath);
+        } catch {
+          // ignore
+        }
+        continue;
+      }
+
+      if (Date.now() - started > INDEX_LOCK_TIMEOUT_MS) {
+        console.error("Timed out waiting for index lock, writing without it");
+        return false;
+      }
+      sleepSync(50);
+    }
+  }
+
+  private isIndexLockStale(): boolean {
+    try {
+      const holder = JSON.parse(readFileSync(this.indexLockPath, "utf-8"));
+      const timestamp = Number(holder.timestamp) || 0;
+      return !isProcessAlive(Number(holder.pid)) || Date.now() - timestamp > INDEX_LOCK_STALE_MS;
+    } catch {
+      // The holder may still be writing its details, so fall back to the file age
+      try {
+        return Date.now() - statSync(this.indexLockPath).mtimeMs > INDEX_LOCK_STALE_MS;
+      } catch {
+        return true;
+      }
+    }
+  }
+
   shouldExclude(filePath: string): boolean {
     return matchesPattern(filePath, this.config.excludePatterns);
   }
@@ -110,7 +217,7 @@ export class BackupManager {
     eventType: FileEventType;
     content?: Buffer;
     renamedTo?: string;
-  }>, message?: string): Snapshot | null {
+  }>, message?: string, trigger?: string): Snapshot | null {
     if (files.length === 0) {
       return null;
     }
@@ -198,10 +305,12 @@ export class BackupManager {
       timestamp,
       files: snapshotFiles,
       message,
+      ...(trigger ? { trigger } : {}),
     };
 
-    this.index.snapshots.push(snapshot);
-    this.saveIndex();
+    this.withIndexLock(() => {
+      this.index.snapshots.push(snapshot);
+    });
 
     return snapshot;
   }
@@ -259,7 +368,7 @@ export class BackupManager {
 
     try {
       if (existsSync(backupFullPath)) {
-        const content = readFileSync(backupFullPath);
+        const content = readBackup(backupFullPath, latest.file);
         return { content, timestamp: latest.snapshot.timestamp };
       }
     } catch {
@@ -293,7 +402,7 @@ export class BackupManager {
           // File was deleted, restore it
           if (existsSync(backupFullPath)) {
             mkdirSync(dirname(targetPath), { recursive: true });
-            copyFileSync(backupFullPath, targetPath);
+            copyBackup(backupFullPath, targetPath, file);
             restored++;
           } else {
             failed++;
@@ -307,7 +416,7 @@ export class BackupManager {
           }
           if (existsSync(backupFullPath)) {
             mkdirSync(dirname(targetPath), { recursive: true });
-            copyFileSync(backupFullPath, targetPath);
+            copyBackup(backupFullPath, targetPath, file);
             restored++;
           } else {
             failed++;
@@ -322,7 +431,7 @@ export class BackupManager {
           // File was modified, restore original version
           if (existsSync(backupFullPath)) {
             mkdirSync(dirname(targetPath), { recursive: true });
-            copyFileSync(backupFullPath, targetPath);
+            copyBackup(backupFullPath, targetPath, file);
             restored++;
           } else if (file.size === 0) {
             // No backup content means the file was new when the snapshot was taken
@@ -375,7 +484,7 @@ export class BackupManager {
 
     try {
       mkdirSync(dirname(targetPath), { recursive: true });
-      copyFileSync(backupFullPath, targetPath);
+      copyBackup(backupFullPath, targetPath, latest.file);
       return true;
     } catch (err) {
       console.error(`Failed to restore ${relativePath}:`, err);
@@ -391,31 +500,32 @@ export class BackupManager {
     let removed = 0;
     let freedBytes = 0;
 
-    const toKeep: Snapshot[] = [];
-
-    for (const snapshot of this.index.snapshots || []) {
-      if (snapshot.timestamp < cutoff) {
-        // Delete backup files in snapshot
-        for (const file of snapshot.files || []) {
-          const backupPath = join(this.snapshotsDir, file.backupPath);
-          try {
-            if (existsSync(backupPath)) {
-              const stats = statSync(backupPath);
-              unlinkSync(backupPath);
-              freedBytes += stats.size;
+    this.withIndexLock(() => {
+      const toKeep: Snapshot[] = [];
+
+      for (const snapshot of this.index.snapshots || []) {
+        if (snapshot.timestamp < cutoff) {
+          // Delete backup files in snapshot
+          for (const file of snapshot.files || []) {
+            const backupPath = join(this.snapshotsDir, file.backupPath);
+            try {
+              if (existsSync(backupPath)) {
+                const stats = statSync(backupPath);
+                unlinkSync(backupPath);
+                freedBytes += stats.size;
+              }
+            } catch {
+              // ignore
             }
-          } catch {
-            // ignore
           }
+          removed++;
+        } else {
+          toKeep.push(snapshot);
         }
-        removed++;
-      } else {
-        toKeep.push(snapshot);
       }
-    }
 
-    this.index.snapshots = toKeep;
-    this.saveIndex();
+      this.index.snapshots = toKeep;
+    });
 
     removeEmptyDirs(this.snapshotsDir);
 
@@ -2,9 +2,9 @@ import { resolve, join } from "path";
 import { existsSync, writeFileSync, readFileSync, unlinkSync, mkdirSync, appendFileSync } from "fs";
 import { spawn, spawnSync } from "child_process";
 import { getDefaultConfig, ShieldConfig } from "./config.js";
-import { BackupManager } from "./backup.js";
+import { BackupManager, FileEventType } from "./backup.js";
 import { ShieldWatcher } from "./watcher.js";
-import { formatBytes, formatTimeAgo } from "./utils.js";
+import { formatBytes, formatTimeAgo, getAllFiles } from "./utils.js";
 
 const SHIELD_VERSION = "0.1.1";
 
@@ -48,6 +48,7 @@ Commands:
   start [path]           Start watching in background (daemon mode)
   stop [path]            Stop background watching
   list                   List all snapshots (time-based versions)
+  snapshot               Record a snapshot of every file in the workspace now
   restore                Restore files from a snapshot
   clean [--days=N]       Remove snapshots older than N days (default: 7)
   status                 Show statistics
@@ -63,11 +64,13 @@ Options:
   --path=<dir>           Specify workspace directory
   --days=<N>             For clean: max age in days
   --file=<path>          For restore: specific file path
+  --message=<text>       For snapshot: description of the snapshot
 
 Examples:
   shield watch ./my-project
   shield start ./my-project
   shield list
+  shield snapshot --message="before refactor"
   shield restore snap_1737216000000   # Restore by snapshot ID
   shield restore 1737216000000        # Restore by timestamp
   shield restore --file=src/index.ts
@@ -96,6 +99,9 @@ export async function runCli(argv: string[]): Promise<void> {
     case "list":
       await cmdList(options);
       break;
+    case "snapshot":
+      await cmdSnapshot(options);
+      break;
     case "clean":
       await cmdClean(options);
       break;
@@ -357,6 +363,37 @@ async function cmdList(options: CliOptions): Promise<void> {
   console.log("\n💡 To restore: shield restore <snapshot_id or timestamp>");
 }
 
+async function cmdSnapshot(options: CliOptions): Promise<void> {
+  const config = await getConfig(options);
+  const backupManager = new BackupManager(config);
+  const message = typeof options.flags["message"] === "string" ? options.flags["message"] : undefined;
+  
+  const changes: Array<{ relativePath: string; eventType: FileEventType; content: Buffer }> = [];
+  for (const file of getAllFiles(config.workspace)) {
+    const relativePath = file.replace(/\\/g, "/");
+    if (relativePath.startsWith(".shield") || backupManager.shouldExclude(relativePath)) {
+      continue;
+    }
+    try {
+      changes.push({
+        relativePath,
+        eventType: "change",
+        content: readFileSync(join(config.workspace, relativePath)),
+      });
+    } catch {
+      // ignore read errors
+    }
+  }
+  
+  const snapshot = backupManager.createSnapshot(changes, message, "manual");
+  if (!snapshot || snapshot.files.length === 0) {
+    console.error("❌ No files to snapshot");
+    process.exit(1);
+  }
+  
+  console.log(`✓ Snapshot created: ${snapshot.id} (${snapshot.files.length} files)`);
+}
+
 async function cmdClean(options: CliOptions): Promise<void> {
   const config = await getConfig(options);
   const backupManager = new BackupManager(config);
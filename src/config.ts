@@ -1,5 +1,5 @@
 import { join } from "path";
-import { readFileSync, existsSync } from "fs";
+import { readFileSync, existsSync, statSync } from "fs";
 
 export interface ShieldConfig {
   workspace: string;
@@ -11,6 +11,7 @@ export interface ShieldConfig {
 export const DEFAULT_VAULT_NAME = ".shield";
 export const SNAPSHOTS_DIR = "snapshots";
 export const INDEX_FILE = "index.json";
+export const INDEX_LOCK_FILE = "index.lock";
 
 export const DEFAULT_EXCLUDE_PATTERNS = [
   "**/.*",
@@ -53,6 +54,25 @@ export const DEFAULT_EXCLUDE_PATTERNS = [
   "**/Library/Caches/**"
 ];
 
+/**
+ * Resolve the vault directory, following a relocation redirect.
+ * When the store has been moved, `.shield` is a plain file holding the new path.
+ */
+export function resolveVaultDir(workspace: string): string {
+  const local = join(workspace, DEFAULT_VAULT_NAME);
+  try {
+    if (statSync(local).isFile()) {
+      const target = readFileSync(local, "utf-8").trim();
+      if (target) {
+        return target;
+      }
+    }
+  } catch {
+    // Missing vault: fall through to the default location
+  }
+  return local;
+}
+
 export function getDefaultConfig(workspace: string): ShieldConfig {
   const excludePatterns = [...DEFAULT_EXCLUDE_PATTERNS];
   
@@ -74,7 +94,7 @@ export function getDefaultConfig(workspace: string): ShieldConfig {
   
   return {
     workspace: workspace,
-    vaultDir: join(workspace, DEFAULT_VAULT_NAME),
+    vaultDir: resolveVaultDir(workspace),
     excludePatterns: excludePatterns,
     maxBackupAgeDays: 7,
   };
@@ -87,3 +107,7 @@ export function getSnapshotsDir(config: ShieldConfig): string {
 export function getIndexPath(config: ShieldConfig): string {
   return join(config.vaultDir, INDEX_FILE);
 }
+
+export function getIndexLockPath(config: ShieldConfig): string {
+  return join(config.vaultDir, INDEX_LOCK_FILE);
+}
@@ -280,7 +280,7 @@ export class ShieldWatcher {
     this.batchTimeout = null;
     
     // create snapshot
-    const snapshot = this.backupManager.createSnapshot(changes);
+    const snapshot = this.backupManager.createSnapshot(changes, undefined, "watch");
     
     if (snapshot) {
       const fileCount = snapshot.files.length;